@@ -0,0 +1,50 @@
+-- Baseline schema
+--
+-- Databases created before migrations were introduced already have (some of) these
+-- tables, so every statement is idempotent.
+
+CREATE TABLE IF NOT EXISTS Dev_Project_Metadata (
+    slug VARCHAR(255) PRIMARY KEY,
+    en_title VARCHAR(500) NOT NULL,
+    en_short_description TEXT NOT NULL,
+    fr_title VARCHAR(500) NOT NULL,
+    fr_short_description TEXT NOT NULL,
+    techs TEXT NOT NULL,
+    link VARCHAR(1000) NOT NULL,
+    date VARCHAR(50) NOT NULL,
+    tags TEXT NOT NULL
+);
+
+ALTER TABLE Dev_Project_Metadata
+    ADD COLUMN IF NOT EXISTS priority INT DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ DEFAULT now(),
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS Album_Metadata (
+    slug VARCHAR(255) PRIMARY KEY,
+    title VARCHAR(500) NOT NULL,
+    description TEXT NOT NULL,
+    short_title VARCHAR(200) NOT NULL,
+    date VARCHAR(50) NOT NULL,
+    camera VARCHAR(200),
+    lens VARCHAR(200),
+    phone VARCHAR(200),
+    preview_img_one_url VARCHAR(1000) NOT NULL,
+    featured BOOLEAN NOT NULL DEFAULT FALSE,
+    category VARCHAR(100) NOT NULL
+);
+
+ALTER TABLE Album_Metadata
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ DEFAULT now(),
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS Album_Content (
+    slug VARCHAR(255) NOT NULL,
+    img_url VARCHAR(1000) NOT NULL,
+    caption TEXT NOT NULL,
+    PRIMARY KEY (slug, img_url),
+    FOREIGN KEY (slug) REFERENCES Album_Metadata(slug) ON DELETE CASCADE
+);
+
+ALTER TABLE Album_Content
+    ADD COLUMN IF NOT EXISTS position INT NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+-- Store project and album dates as DATE so range filters compare dates, not strings
+--
+-- Existing values must be ISO `YYYY-MM-DD` dates, anything else makes this migration fail
+-- and has to be fixed by hand before upgrading.
+
+ALTER TABLE Dev_Project_Metadata
+    ALTER COLUMN date TYPE DATE USING date::date;
+
+ALTER TABLE Album_Metadata
+    ALTER COLUMN date TYPE DATE USING date::date;
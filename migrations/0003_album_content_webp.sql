@@ -0,0 +1,4 @@
+-- WebP copy written next to an uploaded image when CONVERT_TO_WEBP is on
+
+ALTER TABLE Album_Content
+    ADD COLUMN webp_url TEXT;
@@ -0,0 +1,5 @@
+-- Displayed size of album images, so clients can reserve space before they load
+
+ALTER TABLE Album_Content
+    ADD COLUMN width INT,
+    ADD COLUMN height INT;
@@ -0,0 +1,6 @@
+-- Follow album renames: changing Album_Metadata.slug updates the slug of its content
+
+ALTER TABLE Album_Content
+    DROP CONSTRAINT album_content_slug_fkey,
+    ADD CONSTRAINT album_content_slug_fkey
+        FOREIGN KEY (slug) REFERENCES Album_Metadata(slug) ON DELETE CASCADE ON UPDATE CASCADE;
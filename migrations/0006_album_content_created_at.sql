@@ -0,0 +1,8 @@
+-- When each photo was added, for the "recently added" feed
+--
+-- Photos that existed before this migration all get the time it ran.
+
+ALTER TABLE Album_Content
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX album_content_created_at_idx ON Album_Content (created_at DESC);
@@ -0,0 +1,14 @@
+-- Tags of individual photos, for cross-album views like "all photos tagged sunset"
+--
+-- Tags follow their photo when the album is renamed or the photo moved to another
+-- album, and go away with it.
+
+CREATE TABLE Photo_Tags (
+    slug VARCHAR(255) NOT NULL,
+    img_url VARCHAR(1000) NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (slug, img_url, tag),
+    FOREIGN KEY (slug, img_url) REFERENCES Album_Content(slug, img_url) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX photo_tags_tag_idx ON Photo_Tags (tag);
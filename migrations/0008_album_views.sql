@@ -0,0 +1,6 @@
+-- Number of times each album was viewed through GET /albums/{slug}
+
+ALTER TABLE Album_Metadata
+    ADD COLUMN views BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX album_metadata_views_idx ON Album_Metadata (views DESC);
@@ -0,0 +1,8 @@
+-- Soft delete: DELETE /albums/{slug} and DELETE /dev-projects/{slug} set deleted_at,
+-- rows with a deleted_at are hidden until restored or deleted with ?hard=true
+
+ALTER TABLE Album_Metadata
+    ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE Dev_Project_Metadata
+    ADD COLUMN deleted_at TIMESTAMPTZ;
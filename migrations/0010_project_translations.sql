@@ -0,0 +1,23 @@
+-- Project texts per language: a new language is new rows, not new columns
+--
+-- Translations follow their project when it is renamed and go away with it.
+
+CREATE TABLE Project_Translations (
+    slug VARCHAR(255) NOT NULL,
+    lang VARCHAR(35) NOT NULL,
+    title VARCHAR(500) NOT NULL,
+    short_description TEXT NOT NULL,
+    PRIMARY KEY (slug, lang),
+    FOREIGN KEY (slug) REFERENCES Dev_Project_Metadata(slug) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+INSERT INTO Project_Translations (slug, lang, title, short_description)
+SELECT slug, 'en', en_title, en_short_description FROM Dev_Project_Metadata
+UNION ALL
+SELECT slug, 'fr', fr_title, fr_short_description FROM Dev_Project_Metadata;
+
+ALTER TABLE Dev_Project_Metadata
+    DROP COLUMN en_title,
+    DROP COLUMN en_short_description,
+    DROP COLUMN fr_title,
+    DROP COLUMN fr_short_description;
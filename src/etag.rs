@@ -0,0 +1,130 @@
+//! ETag support for the static files under `/files`
+//!
+//! `ServeFile` only sends `Last-Modified`, so files are served through `serve_with_etag`,
+//! which adds a strong ETag built from the file size and modification time, and answers
+//! `304 Not Modified` when the client's `If-None-Match` already holds it. The content type
+//! comes from `media::content_type_for`, and `HEAD` requests get the headers without the body.
+//! SVG and JSON files are sent as their precompressed `.br` or `.gz` copy when there is one
+//! and the client accepts it, under an ETag of their own.
+//!
+//! Uploaded files are stored under a unique name (random suffix or content hash) and never
+//! rewritten, so [`cache_immutable`] also lets browsers and CDNs keep them for a year.
+
+use axum::{
+    extract::Request,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_ENCODING, ETAG, IF_NONE_MATCH, VARY},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{path::Path, time::UNIX_EPOCH};
+use tower::Service;
+use tower_http::services::ServeFile;
+
+use crate::media::{content_type_for, is_precompressible};
+
+/// `Cache-Control` of the files under `/files`, whose URLs always point at the same content
+const IMMUTABLE: HeaderValue = HeaderValue::from_static("public, max-age=31536000, immutable");
+
+/// `Vary` of the files that may be sent precompressed
+const ACCEPT_ENCODING_VARY: HeaderValue = HeaderValue::from_static("accept-encoding");
+
+/// Middleware marking file responses as cacheable for a year without revalidation
+///
+/// Thumbnails, WebP copies and resized variants are derived from their original, so they
+/// get it too. Only `200`, `206` and `304` responses are marked: a `404` for a file that
+/// is not uploaded yet must not stick in caches.
+pub async fn cache_immutable(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    if matches!(response.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED) {
+        response.headers_mut().entry(CACHE_CONTROL).or_insert(IMMUTABLE);
+    }
+    response
+}
+
+/// Serve the file at `path` (with `Range` and `Last-Modified` support), adding an `ETag`
+///
+/// A missing file is a `404 Not Found`, for `HEAD` requests too.
+pub async fn serve_with_etag(path: &Path, request: Request) -> Response {
+    let etag = match *request.method() {
+        Method::GET | Method::HEAD => etag_for(path).await,
+        _ => None,
+    };
+
+    let precompressible = is_precompressible(path);
+    if let Some(etag) = &etag {
+        let mut candidates = vec![etag.clone()];
+        if precompressible {
+            candidates.extend(["gzip", "br"].iter().filter_map(|encoding| encoded_etag(etag, encoding)));
+        }
+        if let Some(matched) = candidates.into_iter().find(|etag| if_none_match(request.headers(), etag)) {
+            let mut response = (StatusCode::NOT_MODIFIED, [(ETAG, matched)]).into_response();
+            if precompressible {
+                response.headers_mut().insert(VARY, ACCEPT_ENCODING_VARY);
+            }
+            return response;
+        }
+    }
+
+    let mut service = ServeFile::new_with_mime(path, &content_type_for(path));
+    if precompressible {
+        service = service.precompressed_br().precompressed_gzip();
+    }
+    let served = match std::future::poll_fn(|cx| Service::<Request>::poll_ready(&mut service, cx)).await {
+        Ok(()) => service.call(request).await,
+        Err(never) => match never {},
+    };
+    let mut response = match served {
+        Ok(response) => response.into_response(),
+        Err(never) => match never {},
+    };
+    // The compression layer leaves encoded responses alone, so it does not add `Vary` to them
+    let encoding = response.headers().get(CONTENT_ENCODING).cloned();
+    let etag = match (etag, &encoding) {
+        (Some(etag), Some(encoding)) => encoding.to_str().ok().and_then(|encoding| encoded_etag(&etag, encoding)),
+        (etag, _) => etag,
+    };
+    if encoding.is_some() {
+        response.headers_mut().append(VARY, ACCEPT_ENCODING_VARY);
+    }
+    if let Some(etag) = etag {
+        if matches!(response.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT) {
+            response.headers_mut().insert(ETAG, etag);
+        }
+    }
+    response
+}
+
+/// ETag of a precompressed copy, which differs from the file's (`"1a-2b"` -> `"1a-2b-br"`)
+fn encoded_etag(etag: &HeaderValue, encoding: &str) -> Option<HeaderValue> {
+    let etag = etag.to_str().ok()?.strip_suffix('"')?;
+    HeaderValue::from_str(&format!("{}-{}\"", etag, encoding)).ok()
+}
+
+/// Compute the ETag of the file at `path`
+async fn etag_for(path: &Path) -> Option<HeaderValue> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+    HeaderValue::from_str(&format!("\"{:x}-{:x}\"", metadata.len(), modified.as_nanos())).ok()
+}
+
+/// Whether the `If-None-Match` header lists `etag` (or is `*`)
+fn if_none_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(etag) = etag.to_str().ok() else {
+        return false;
+    };
+
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
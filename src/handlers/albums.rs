@@ -4,141 +4,765 @@
 //! It provides endpoints for listing albums and retrieving album details with content.
 
 use axum::{
-    extract::{Multipart, Path, State},
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{multipart::Field, Multipart, Path, Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, LAST_MODIFIED},
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Json, Response},
+    Extension,
 };
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tracing::{error, info};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, PgPool, Postgres, Transaction};
+use std::{collections::HashMap, path::PathBuf};
+use tokio::{
+    fs,
+    io::{AsyncWrite, AsyncWriteExt},
+};
+use tokio_util::io::ReaderStream;
+use tracing::{error, info, warn};
 use utoipa;
-use uuid::Uuid;
 
-use crate::{database, models::*, AppState};
+use crate::{
+    database::{self, DateRange},
+    error::ApiError,
+    handlers::{created_at, date_range, parse_date, slugify, validate_slug, ListFormat},
+    media::{
+        file_path, process_image, read_exif, thumbnail_path, thumbnail_url, thumbnail_variant_path,
+        thumbnail_variants, precompressed_paths, webp_path, write_precompressed, write_webp_copy,
+        RESIZE_CACHE_DIR,
+    },
+    middleware::{ApiKeyLabel, RequireAdmin},
+    models::*,
+    uploads::{multipart_error, StagedFile, StagingDir, StoredFile},
+    zip_stream::ZipStreamWriter,
+    AppState,
+};
+
+/// Size of the buffer between the ZIP writer and the response body
+const ZIP_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Number of an album's first photos tried for a stand-in when its cover file is missing
+const COVER_CANDIDATES: usize = 10;
 
 /// Get all photo albums
 ///
-/// Returns a list of all photo albums in the portfolio
+/// Returns a list of all photo albums in the portfolio, with their photo count.
+/// Use `?from=2024-01-01&to=2024-12-31` to only list albums dated within that range (inclusive),
+/// and `?featured=true` (or `false`) to only list featured (or other) albums.
 #[utoipa::path(
     get,
     path = "/albums",
+    params(AlbumFilter, EnvelopeQuery),
     responses(
-        (status = 200, description = "List of photo albums with their content", body = [AlbumWithContent]),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "List of photo albums with their content", content(
+            ("application/json" = [AlbumListItem]),
+            ("application/vnd.portfolio.v2+json" = AlbumList)
+        )),
+        (status = 400, description = "Invalid date filter", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     tag = "Photo Albums"
 )]
 pub async fn get_albums(
     State(state): State<AppState>,
-) -> Result<Json<Vec<AlbumWithContent>>, StatusCode> {
-    match database::get_all_albums(&state.db).await {
-        Ok(albums) => Ok(Json(albums)),
+    format: ListFormat,
+    Query(filter): Query<AlbumFilter>,
+) -> Result<Response, ApiError> {
+    let range = date_range(filter.from.as_deref(), filter.to.as_deref())?;
+    list_albums(&state, format, range, filter.featured).await
+}
+
+/// Get the featured albums
+///
+/// Returns the albums marked as `featured`, newest first, like `GET /albums?featured=true`.
+/// An empty list when no album is featured.
+#[utoipa::path(
+    get,
+    path = "/albums/featured",
+    params(EnvelopeQuery),
+    responses(
+        (status = 200, description = "Featured photo albums with their content", content(
+            ("application/json" = [AlbumListItem]),
+            ("application/vnd.portfolio.v2+json" = AlbumList)
+        )),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_featured_albums(State(state): State<AppState>, format: ListFormat) -> Result<Response, ApiError> {
+    list_albums(&state, format, DateRange::default(), Some(true)).await
+}
+
+async fn list_albums(
+    state: &AppState,
+    format: ListFormat,
+    range: DateRange,
+    featured: Option<bool>,
+) -> Result<Response, ApiError> {
+    match database::get_all_albums(&state.db, range, featured).await {
+        Ok(mut albums) => {
+            for album in &mut albums {
+                let missing = !cover_exists(state, &album.metadata.preview_img_one_url).await;
+                let candidates = &album.content[..album.content.len().min(COVER_CANDIDATES)];
+                resolve_cover(state, &mut album.metadata, missing, candidates).await;
+            }
+            let content = albums.iter_mut().flat_map(|album| album.content.iter_mut());
+            add_thumbnail_variants(content, &state.config.thumbnail_sizes);
+            Ok(format.respond(albums))
+        }
         Err(e) => {
             error!("Failed to fetch albums: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to fetch albums"))
+        }
+    }
+}
+
+/// Get all album categories
+///
+/// Returns the distinct categories used by albums, sorted alphabetically
+#[utoipa::path(
+    get,
+    path = "/albums/categories",
+    params(EnvelopeQuery),
+    responses(
+        (status = 200, description = "List of album categories", content(
+            ("application/json" = CategoryListResponse),
+            ("application/vnd.portfolio.v2+json" = CategoryList)
+        )),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_album_categories(
+    State(state): State<AppState>,
+    format: ListFormat,
+) -> Result<Response, ApiError> {
+    match database::get_album_categories(&state.db).await {
+        Ok(categories) => Ok(format.respond(categories)),
+        Err(e) => {
+            error!("Failed to fetch album categories: {}", e);
+            Err(ApiError::internal("Failed to fetch album categories"))
+        }
+    }
+}
+
+/// Number of photos returned by `GET /albums/recent` without a `limit`
+const DEFAULT_RECENT_LIMIT: u32 = 20;
+
+/// Largest `limit` accepted by `GET /albums/recent`, bigger values are capped
+const MAX_RECENT_LIMIT: u32 = 50;
+
+/// Get recently added photos
+///
+/// Returns the most recently added photos across all albums, newest first, each with the
+/// title of its album. `limit` defaults to 20 and is capped at 50.
+#[utoipa::path(
+    get,
+    path = "/albums/recent",
+    params(RecentQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "Recently added photos, newest first", content(
+            ("application/json" = [RecentPhoto]),
+            ("application/vnd.portfolio.v2+json" = RecentPhotoList)
+        )),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_recent_photos(
+    State(state): State<AppState>,
+    format: ListFormat,
+    Query(query): Query<RecentQuery>,
+) -> Result<Response, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_RECENT_LIMIT).clamp(1, MAX_RECENT_LIMIT);
+
+    match database::get_recent_album_content(&state.db, i64::from(limit)).await {
+        Ok(mut photos) => {
+            add_thumbnail_variants(photos.iter_mut().map(|photo| &mut photo.content), &state.config.thumbnail_sizes);
+            Ok(format.respond(photos))
+        }
+        Err(e) => {
+            error!("Failed to fetch recent photos: {}", e);
+            Err(ApiError::internal("Failed to fetch recent photos"))
+        }
+    }
+}
+
+/// Count a view of an album in the background, so the read does not wait for the write
+fn count_view(db: PgPool, slug: String) {
+    tokio::spawn(async move {
+        if let Err(e) = database::increment_album_views(&db, &slug).await {
+            warn!("Failed to count a view of album {}: {}", slug, e);
+        }
+    });
+}
+
+/// Format a time as an HTTP date (`Sun, 06 Nov 1994 08:49:37 GMT`)
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Whether the `If-Modified-Since` header is at or after `modified`
+///
+/// HTTP dates have a one second resolution, so `modified` is truncated to the second.
+/// An unparseable header is ignored.
+fn not_modified_since(headers: &HeaderMap, modified: DateTime<Utc>) -> bool {
+    headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| since.timestamp() >= modified.timestamp())
+}
+
+/// Strong ETag of an album version (see `database::album_version`), sent by `GET /albums/{slug}`
+/// and checked against `If-Match` by `PUT /albums/{slug}`
+fn album_etag(version: i64) -> HeaderValue {
+    let hash = Sha256::digest(version.to_be_bytes());
+    let hex: String = hash[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+    HeaderValue::from_str(&format!("\"{}\"", hex)).expect("hex ETag is a valid header value")
+}
+
+/// Whether the `If-Match` header lists `etag` (or is `*`)
+///
+/// The comparison is strong: weak (`W/`) ETags never match.
+fn if_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+
+    headers
+        .get_all(IF_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate == etag)
+}
+
+/// Number of albums returned by `GET /albums/popular` without a `limit`
+const DEFAULT_POPULAR_LIMIT: u32 = 10;
+
+/// Largest `limit` accepted by `GET /albums/popular`, bigger values are capped
+const MAX_POPULAR_LIMIT: u32 = 50;
+
+/// Get the most viewed albums
+///
+/// Albums ordered by `views`, most viewed first. `limit` defaults to 10 and is capped at 50.
+#[utoipa::path(
+    get,
+    path = "/albums/popular",
+    params(PopularQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "Most viewed albums", content(
+            ("application/json" = Vec<Album_Metadata>),
+            ("application/vnd.portfolio.v2+json" = AlbumMetadataList)
+        )),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_popular_albums(
+    State(state): State<AppState>,
+    format: ListFormat,
+    Query(query): Query<PopularQuery>,
+) -> Result<Response, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_POPULAR_LIMIT).clamp(1, MAX_POPULAR_LIMIT);
+
+    database::get_popular_albums(&state.db, i64::from(limit))
+        .await
+        .map(|albums| format.respond(albums))
+        .map_err(|e| {
+            error!("Failed to fetch popular albums: {}", e);
+            ApiError::internal("Failed to fetch popular albums")
+        })
+}
+
+/// Largest number of slugs accepted by `GET /albums/batch`
+const MAX_BATCH_SLUGS: usize = 100;
+
+/// Get several albums by slug
+///
+/// Returns the metadata of the albums named in `?slugs=a,b,c`, in that order, in one request.
+/// Slugs without an album (or with a trashed one) are listed in `missing`; repeated slugs are
+/// returned once.
+#[utoipa::path(
+    get,
+    path = "/albums/batch",
+    params(AlbumBatchQuery),
+    responses(
+        (status = 200, description = "The requested albums", body = AlbumBatchResponse),
+        (status = 400, description = "No slugs or more than 100", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_albums_batch(
+    State(state): State<AppState>,
+    Query(query): Query<AlbumBatchQuery>,
+) -> Result<Json<AlbumBatchResponse>, ApiError> {
+    let mut slugs: Vec<String> = Vec::new();
+    for slug in query.slugs.as_deref().unwrap_or("").split(',').map(str::trim) {
+        if !slug.is_empty() && !slugs.iter().any(|known| known == slug) {
+            slugs.push(slug.to_string());
+        }
+    }
+
+    if slugs.is_empty() {
+        return Err(ApiError::bad_request("MISSING_SLUGS", "slugs must list at least one album slug"));
+    }
+    if slugs.len() > MAX_BATCH_SLUGS {
+        return Err(ApiError::bad_request(
+            "TOO_MANY_SLUGS",
+            format!("At most {} albums can be fetched at once", MAX_BATCH_SLUGS),
+        ));
+    }
+
+    let albums = database::get_albums_by_slugs(&state.db, &slugs).await.map_err(|e| {
+        error!("Failed to fetch albums: {}", e);
+        ApiError::internal("Failed to fetch albums")
+    })?;
+    let missing = slugs
+        .into_iter()
+        .filter(|slug| !albums.iter().any(|album| &album.slug == slug))
+        .collect();
+
+    Ok(Json(AlbumBatchResponse { albums, missing }))
+}
+
+/// Get a random album
+///
+/// Returns a random featured album with its content, or a random album when none is
+/// featured. Meant for "featured today" widgets, so the response is not cacheable.
+#[utoipa::path(
+    get,
+    path = "/albums/random",
+    responses(
+        (status = 200, description = "Random album with content", body = AlbumWithContent),
+        (status = 404, description = "There are no albums", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_random_album(
+    State(state): State<AppState>,
+) -> Result<([(HeaderName, &'static str); 1], Json<AlbumWithContent>), ApiError> {
+    match database::get_random_album(&state.db).await {
+        Ok(Some(mut album)) => {
+            add_thumbnail_variants(album.content.iter_mut(), &state.config.thumbnail_sizes);
+            Ok(([(CACHE_CONTROL, "no-store")], Json(album)))
+        }
+        Ok(None) => Err(ApiError::not_found("ALBUM_NOT_FOUND", "There are no albums yet")),
+        Err(e) => {
+            error!("Failed to fetch random album: {}", e);
+            Err(ApiError::internal("Failed to fetch random album"))
+        }
+    }
+}
+
+/// Get a random photo
+///
+/// Returns a random photo from any album, its `slug` is the album it belongs to. The
+/// response is not cacheable.
+#[utoipa::path(
+    get,
+    path = "/photos/random",
+    responses(
+        (status = 200, description = "Random photo", body = Album_Content),
+        (status = 404, description = "There are no photos", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_random_photo(
+    State(state): State<AppState>,
+) -> Result<([(HeaderName, &'static str); 1], Json<Album_Content>), ApiError> {
+    match database::get_random_album_content(&state.db).await {
+        Ok(Some(mut photo)) => {
+            add_thumbnail_variants(std::iter::once(&mut photo), &state.config.thumbnail_sizes);
+            Ok(([(CACHE_CONTROL, "no-store")], Json(photo)))
+        }
+        Ok(None) => Err(ApiError::not_found("PHOTO_NOT_FOUND", "There are no photos yet")),
+        Err(e) => {
+            error!("Failed to fetch random photo: {}", e);
+            Err(ApiError::internal("Failed to fetch random photo"))
+        }
+    }
+}
+
+/// Search photo albums
+///
+/// Full-text search over album titles, descriptions and photo captions. Every word of `q`
+/// must appear (whole words, case-insensitive). Albums are ranked so that a match in the
+/// title outweighs one in the description, which outweighs one in a caption; each matching
+/// caption adds to the album's rank. Matching photos are returned with the matched words
+/// wrapped in `<mark>` tags.
+#[utoipa::path(
+    get,
+    path = "/albums/search",
+    params(SearchQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "Matching albums, most relevant first", content(
+            ("application/json" = [AlbumSearchResult]),
+            ("application/vnd.portfolio.v2+json" = AlbumSearchResultList)
+        )),
+        (status = 400, description = "Missing or empty search query", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn search_albums(
+    State(state): State<AppState>,
+    format: ListFormat,
+    Query(search): Query<SearchQuery>,
+) -> Result<Response, ApiError> {
+    let query = search.q.as_deref().map(str::trim).unwrap_or_default();
+    if query.is_empty() {
+        return Err(ApiError::bad_request("MISSING_QUERY", "Search query 'q' must not be empty"));
+    }
+
+    match database::search_albums(&state.db, query).await {
+        Ok(mut albums) => {
+            let content = albums
+                .iter_mut()
+                .flat_map(|album| album.matched_photos.iter_mut())
+                .map(|photo| &mut photo.content);
+            add_thumbnail_variants(content, &state.config.thumbnail_sizes);
+            Ok(format.respond(albums))
+        }
+        Err(e) => {
+            error!("Failed to search albums: {}", e);
+            Err(ApiError::internal("Failed to search albums"))
         }
     }
 }
 
 /// Get a specific photo album with its content
 ///
-/// Returns detailed information about a photo album including all its images
+/// Returns detailed information about a photo album including its images. `photo_limit`
+/// and `photo_offset` page through the photos of large albums, the album metadata and
+/// `total_photos` are always returned.
+///
+/// Each request counts as a view, after the response is sent (`views` does not include
+/// it). Admin previews pass `track=false` to be left out.
+///
+/// `Last-Modified` is the latest of the album's last update and the time its newest photo
+/// was added. A request whose `If-Modified-Since` is at or after it gets an empty
+/// `304 Not Modified`, which is not counted as a view. While the cover file is missing from
+/// disk and replaced (see `raw_preview_url`), the full album is always sent, since the
+/// stand-in does not change `Last-Modified`.
+///
+/// The `ETag` identifies the version of the album, to send back in `If-Match` when updating it.
 #[utoipa::path(
     get,
     path = "/albums/{slug}",
     responses(
-        (status = 200, description = "Album with content", body = AlbumWithContent),
-        (status = 404, description = "Album not found"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Album with content", body = AlbumPage,
+            headers(("ETag" = String, description = "Version of the album, for If-Match on updates"))),
+        (status = 304, description = "Album unchanged since If-Modified-Since"),
+        (status = 400, description = "Invalid paging or sort parameter", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
-        ("slug" = String, Path, description = "Album slug identifier")
+        ("slug" = String, Path, description = "Album slug identifier"),
+        AlbumQuery
     ),
     tag = "Photo Albums"
 )]
 pub async fn get_album(
     State(state): State<AppState>,
     Path(slug): Path<String>,
-) -> Result<Json<AlbumWithContent>, StatusCode> {
-    match database::get_album_with_content(&state.db, &slug).await {
-        Ok(Some(album)) => Ok(Json(album)),
-        Ok(None) => Err(StatusCode::NOT_FOUND),
+    Query(query): Query<AlbumQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let limit = query.photo_limit.map(i64::from);
+    let offset = i64::from(query.photo_offset.unwrap_or(0));
+
+    let validators = match database::album_validators(&state.db, &slug).await {
+        Ok(Some(validators)) => validators,
+        Ok(None) => return Err(album_not_found(&slug)),
+        Err(e) => {
+            error!("Failed to fetch album: {}", e);
+            return Err(ApiError::internal("Failed to fetch album"));
+        }
+    };
+    let modified = validators.modified;
+
+    let cover_url = &validators.preview_img_one_url;
+    let cover_missing = !cover_exists(&state, cover_url).await;
+    // An unset cover is replaced from what the database holds, which `Last-Modified` follows,
+    // but not a cover file gone from disk
+    let cover_file_gone = cover_missing && !cover_url.is_empty();
+
+    if let Some(modified) = modified.filter(|_| !cover_file_gone) {
+        if not_modified_since(&headers, modified) {
+            // A 304 repeats the validators the full response would have sent
+            let mut response = (StatusCode::NOT_MODIFIED, [(LAST_MODIFIED, http_date(modified))]).into_response();
+            response.headers_mut().insert(ETAG, album_etag(validators.version));
+            return Ok(response);
+        }
+    }
+
+    // The stand-in cover comes from the album's first photos, whatever page is asked for
+    let cover_candidates = if cover_missing {
+        database::first_album_photos(&state.db, &slug, COVER_CANDIDATES as i64).await.map_err(|e| {
+            error!("Failed to fetch album: {}", e);
+            ApiError::internal("Failed to fetch album")
+        })?
+    } else {
+        Vec::new()
+    };
+
+    match database::get_album_page(&state.db, &slug, query.sort, limit, offset).await {
+        Ok(Some(mut page)) => {
+            resolve_cover(&state, &mut page.album.metadata, cover_missing, &cover_candidates).await;
+            add_thumbnail_variants(page.album.content.iter_mut(), &state.config.thumbnail_sizes);
+            if query.track.unwrap_or(true) {
+                count_view(state.db.clone(), slug);
+            }
+            let mut response = Json(page).into_response();
+            if let Some(last_modified) = modified.and_then(|modified| HeaderValue::from_str(&http_date(modified)).ok()) {
+                response.headers_mut().insert(LAST_MODIFIED, last_modified);
+            }
+            response.headers_mut().insert(ETAG, album_etag(validators.version));
+            Ok(response)
+        }
+        Ok(None) => Err(album_not_found(&slug)),
+        Err(e) => {
+            error!("Failed to fetch album: {}", e);
+            Err(ApiError::internal("Failed to fetch album"))
+        }
+    }
+}
+
+/// Get one photo of an album
+///
+/// Returns a single photo with its caption, dimensions, thumbnail URLs, WebP copy and tags,
+/// e.g. for a lightbox loading the details of the photo it shows. `filename` is the last
+/// part of the photo's `img_url` (`/files/{slug}/{filename}`).
+#[utoipa::path(
+    get,
+    path = "/albums/{slug}/photos/{filename}",
+    responses(
+        (status = 200, description = "The photo", body = Album_Content),
+        (status = 404, description = "Album not found, or the photo is not part of it", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier"),
+        ("filename" = String, Path, description = "Filename of the photo in the album")
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_album_photo(
+    State(state): State<AppState>,
+    Path((slug, filename)): Path<(String, String)>,
+) -> Result<Json<Album_Content>, ApiError> {
+    let img_url = format!("/files/{}/{}", slug, filename);
+
+    match database::get_album_photo(&state.db, &slug, &img_url).await {
+        Ok(Some(mut photo)) => {
+            add_thumbnail_variants(std::iter::once(&mut photo), &state.config.thumbnail_sizes);
+            Ok(Json(photo))
+        }
+        Ok(None) => match database::album_exists(&state.db, &slug).await {
+            Ok(true) => Err(photo_not_found(&slug, &img_url)),
+            Ok(false) => Err(album_not_found(&slug)),
+            Err(e) => {
+                error!("Failed to check album existence: {}", e);
+                Err(ApiError::internal("Failed to fetch photo"))
+            }
+        },
+        Err(e) => {
+            error!("Failed to fetch photo {} of album {}: {}", img_url, slug, e);
+            Err(ApiError::internal("Failed to fetch photo"))
+        }
+    }
+}
+
+/// Download a photo album as a ZIP archive
+///
+/// Streams a ZIP archive of the album's original files (no thumbnails), built on the fly.
+/// Files missing from disk are left out of the archive.
+#[utoipa::path(
+    get,
+    path = "/albums/{slug}/download",
+    responses(
+        (status = 200, description = "ZIP archive of the album files", content_type = "application/zip"),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier")
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn download_album(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Response, ApiError> {
+    let album = match database::get_album_with_content(&state.db, &slug).await {
+        Ok(Some(album)) => album,
+        Ok(None) => return Err(album_not_found(&slug)),
         Err(e) => {
             error!("Failed to fetch album: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            return Err(ApiError::internal("Failed to fetch album"));
+        }
+    };
+
+    let (writer, reader) = tokio::io::duplex(ZIP_BUFFER_BYTES);
+    let upload_dir = state.config.upload_dir.clone();
+    let archive_slug = slug.clone();
+    tokio::spawn(async move {
+        // On failure the client receives a truncated archive
+        if let Err(e) = write_album_zip(&upload_dir, &album.content, writer).await {
+            error!("Failed to write ZIP archive of album '{}': {}", archive_slug, e);
         }
+    });
+
+    let filename = slug.replace(|c: char| c == '"' || c == '\\' || c.is_control(), "_");
+    Ok((
+        [
+            (CONTENT_TYPE, "application/zip".to_string()),
+            (CONTENT_DISPOSITION, format!("attachment; filename=\"{}.zip\"", filename)),
+        ],
+        Body::from_stream(ReaderStream::new(reader)),
+    )
+        .into_response())
+}
+
+/// Write the files of an album to a ZIP archive, skipping the ones missing from disk
+async fn write_album_zip(
+    upload_dir: &std::path::Path,
+    content: &[Album_Content],
+    writer: impl AsyncWrite + Unpin,
+) -> std::io::Result<()> {
+    let mut zip = ZipStreamWriter::new(writer);
+
+    for photo in content {
+        let Some(path) = file_path(upload_dir, &photo.img_url) else {
+            warn!("Leaving '{}' out of the ZIP archive: not an uploaded file", photo.img_url);
+            continue;
+        };
+        let file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Leaving {:?} out of the ZIP archive: {}", path, e);
+                continue;
+            }
+        };
+
+        let modified = file
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        zip.add_file(&name, modified, file).await?;
     }
+
+    zip.finish().await?.shutdown().await
 }
 
 /// Create a new album
 ///
 /// Create a new photo album in the portfolio
+///
+/// When `slug` is omitted it is derived from `title` (`"Été à Paris"` becomes `ete-a-paris`),
+/// with `-2`, `-3`, ... appended if needed. The response holds the final slug.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     post,
     path = "/albums",
     request_body = CreateAlbumRequest,
     responses(
-        (status = 201, description = "Album created successfully", body = AlbumOperationResponse),
-        (status = 400, description = "Invalid request data"),
-        (status = 409, description = "Album with this slug already exists"),
-        (status = 500, description = "Internal server error")
+        (status = 201, description = "Album created successfully", body = AlbumOperationResponse,
+            headers(("Location" = String, description = "URL of the new album"))),
+        (status = 400, description = "Invalid request data", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 409, description = "Album with this slug already exists, or the Idempotency-Key is used by a running request", body = ErrorResponse),
+        (status = 429, description = "Too many upload requests from this IP", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replay the response of an earlier successful request sent with the same key instead of creating again")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Photo Albums"
 )]
 pub async fn create_album(
     State(state): State<AppState>,
     Json(request): Json<CreateAlbumRequest>,
-) -> Result<Json<AlbumOperationResponse>, StatusCode> {
-    // Check if album with this slug already exists
-    match database::album_exists(&state.db, &request.slug).await {
-        Ok(true) => {
-            return Err(StatusCode::CONFLICT);
-        }
-        Ok(false) => {} // OK, album doesn't exist
-        Err(e) => {
-            error!("Failed to check existing album: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    }
+) -> Result<(StatusCode, HeaderMap, Json<AlbumOperationResponse>), ApiError> {
+    let slug = album_slug(&state, request.slug.as_deref(), &request.title).await?;
+
+    let date = request
+        .date
+        .ok_or_else(|| ApiError::bad_request("MISSING_FIELD", "Missing required date field"))?;
+    let date = parse_date("date", &date)?.to_string();
+    validate_cover_url(&state, &request.preview_img_one_url).await?;
 
     // Convert request to Album_Metadata
     let album = Album_Metadata {
-        slug: request.slug.clone(),
+        slug: slug.clone(),
         title: request.title,
         description: request.description,
         short_title: request.short_title,
-        date: request.date,
+        date,
         camera: request.camera,
         lens: request.lens,
         phone: request.phone,
         preview_img_one_url: request.preview_img_one_url,
+        raw_preview_url: None,
         featured: request.featured,
         category: request.category,
+        views: 0,
+        created_at: None,
+        updated_at: None,
     };
 
+    // Insert the album before touching the filesystem so a concurrent create of the
+    // same slug fails on the primary key without leaving a directory behind
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        ApiError::internal("Failed to create album")
+    })?;
+
+    if let Err(e) = database::create_album(&mut *tx, &album).await {
+        return Err(create_album_error(&state, &slug, e).await);
+    }
+
     // Create album directory
-    let album_dir = state.upload_dir.join(&request.slug);
+    let album_dir = state.config.upload_dir.join(&slug);
     if let Err(e) = fs::create_dir_all(&album_dir).await {
         error!("Failed to create album directory {}: {}", album_dir.display(), e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::internal("Failed to create album directory"));
     }
 
-    match database::create_album(&state.db, &album).await {
+    match tx.commit().await {
         Ok(_) => {
-            info!("Created album: {}", request.slug);
-            Ok(Json(AlbumOperationResponse {
-                message: "Album created successfully".to_string(),
-                slug: request.slug,
-            }))
+            info!("Created album: {}", slug);
+            Ok((
+                StatusCode::CREATED,
+                created_at("/albums", &slug),
+                Json(AlbumOperationResponse {
+                    message: "Album created successfully".to_string(),
+                    slug,
+                }),
+            ))
         }
         Err(e) => {
-            error!("Failed to create album: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            error!("Failed to commit album {}: {}", slug, e);
+            Err(ApiError::internal("Failed to create album"))
         }
     }
 }
@@ -146,11 +770,17 @@ pub async fn create_album(
 /// Create a new album with files
 ///
 /// Create a new photo album and upload files to it in one operation
+///
+/// `camera`, `lens` and `date` left null in `album_data` are filled from the EXIF data
+/// (`Make`/`Model`, `LensModel`, `DateTimeOriginal`) of the first uploaded image.
+/// `preview_img_one_url` can be the filename of one of the uploaded files, it is replaced
+/// by the URL that file is stored under.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 /// 
 /// Required form fields:
 /// - `album_data`: Album metadata as JSON string
+/// - `captions`: Optional per-file captions, a JSON array (upload order) or object (filename → caption)
 /// - `files`: Files to upload (binary, can be multiple files)
 #[utoipa::path(
     post,
@@ -160,198 +790,249 @@ pub async fn create_album(
         content_type = "multipart/form-data"
     ),
     responses(
-        (status = 201, description = "Album created with files successfully", body = AddPhotosResponse),
-        (status = 400, description = "Bad request - invalid data or missing fields"),
-        (status = 409, description = "Album with this slug already exists"),
-        (status = 401, description = "Unauthorized - invalid or missing API key"),
-        (status = 500, description = "Internal server error")
+        (status = 201, description = "Album created with files successfully", body = AddPhotosResponse,
+            headers(("Location" = String, description = "URL of the new album"))),
+        (status = 400, description = "Bad request - invalid data or missing fields, or more files than `MAX_PHOTOS_PER_ALBUM`", body = ErrorResponse),
+        (status = 409, description = "Album with this slug already exists, or the Idempotency-Key is used by a running request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 413, description = "Payload too large - a file or the request exceeds the upload size limit", body = ErrorResponse),
+        (status = 415, description = "Unsupported media type - file content is not in the allowlist", body = ErrorResponse),
+        (status = 429, description = "Too many upload requests from this IP", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replay the response of an earlier successful request sent with the same key instead of creating again")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Photo Albums"
 )]
 pub async fn create_album_with_files(
     State(state): State<AppState>,
     mut multipart: Multipart,
-) -> Result<Json<AddPhotosResponse>, StatusCode> {
+) -> Result<(StatusCode, HeaderMap, Json<AddPhotosResponse>), ApiError> {
+    let staging = StagingDir::create(&state.config.upload_dir).await?;
     let mut album_data: Option<String> = None;
-    let mut file_data: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut captions: Option<PhotoCaptions> = None;
+    let mut staged_files: Vec<StagedFile> = Vec::new();
 
-    // Collect all fields
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error!("Failed to read multipart field: {}", e);
-        StatusCode::BAD_REQUEST
-    })? {
+    // Stream files to the staging directory and collect the other fields
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
         let name = field.name().unwrap_or("");
 
         if name == "album_data" {
             let data = field.bytes().await.map_err(|e| {
                 error!("Failed to read album data: {}", e);
-                StatusCode::BAD_REQUEST
+                ApiError::bad_request("INVALID_MULTIPART", "Failed to read album_data field")
             })?;
             album_data = Some(String::from_utf8(data.to_vec()).map_err(|e| {
                 error!("Invalid UTF-8 in album data: {}", e);
-                StatusCode::BAD_REQUEST
+                ApiError::bad_request("INVALID_ALBUM_DATA", "album_data must be valid UTF-8")
             })?);
+        } else if name == "captions" {
+            captions = Some(read_captions_field(field).await?);
         } else if name == "files" {
             let filename = field.file_name().unwrap_or("unknown").to_string();
-            let data = field.bytes().await.map_err(|e| {
-                error!("Failed to read file data: {}", e);
-                StatusCode::BAD_REQUEST
-            })?;
-            file_data.push((filename, data.to_vec()));
+            let staged = staging
+                .stage_field(field, filename, state.config.max_upload_bytes, &state.config.allowed_mime_types)
+                .await?;
+            info!("Received file: {} ({}, {} bytes)", staged.filename, staged.mime_type, staged.size);
+            staged_files.push(staged);
         }
     }
 
+    if staged_files.len() > state.config.max_photos_per_album {
+        return Err(ApiError::bad_request(
+            "TOO_MANY_PHOTOS",
+            format!(
+                "An album can hold at most {} photos, {} files were sent",
+                state.config.max_photos_per_album,
+                staged_files.len()
+            ),
+        ));
+    }
+
+    let file_captions = match captions {
+        Some(captions) => resolve_captions(captions, &staged_files)?,
+        None => vec![None; staged_files.len()],
+    };
+
     // Parse album data
     let album_json = album_data.ok_or_else(|| {
         error!("No album data provided");
-        StatusCode::BAD_REQUEST
+        ApiError::bad_request("MISSING_FIELD", "Missing required album_data field")
     })?;
 
-    let album_request: CreateAlbumRequest = serde_json::from_str(&album_json).map_err(|e| {
+    let mut album_request: CreateAlbumRequest = serde_json::from_str(&album_json).map_err(|e| {
         error!("Failed to parse album data: {}", e);
-        StatusCode::BAD_REQUEST
+        ApiError::bad_request("INVALID_ALBUM_DATA", format!("Invalid album_data: {}", e))
     })?;
+    let slug = album_slug(&state, album_request.slug.as_deref(), &album_request.title).await?;
 
-    // Check if album exists
-    match database::album_exists(&state.db, &album_request.slug).await {
-        Ok(true) => return Err(StatusCode::CONFLICT),
-        Ok(false) => {},
-        Err(e) => {
-            error!("Failed to check existing album: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    // Fill the camera details the client left out from the first uploaded image,
+    // explicit values always win over EXIF
+    if album_request.camera.is_none() || album_request.lens.is_none() || album_request.date.is_none() {
+        if let Some(image) = staged_files.iter().find(|f| f.mime_type.starts_with("image/")) {
+            let exif = read_exif(image.path()).await;
+            album_request.camera = album_request.camera.or(exif.camera);
+            album_request.lens = album_request.lens.or(exif.lens);
+            album_request.date = album_request.date.or(exif.date);
         }
     }
 
+    let date = album_request.date.ok_or_else(|| {
+        ApiError::bad_request(
+            "MISSING_FIELD",
+            "Missing required date field (not found in the EXIF data of the uploaded photos either)",
+        )
+    })?;
+    let date = parse_date("date", &date)?.to_string();
+
+    // The cover may name one of the uploaded files, its URL is only known once it is stored
+    let cover_file = staged_files
+        .iter()
+        .any(|staged| staged.filename == album_request.preview_img_one_url)
+        .then(|| album_request.preview_img_one_url.clone());
+    if cover_file.is_none() {
+        validate_cover_url(&state, &album_request.preview_img_one_url).await?;
+    }
+
     // Create album
     let album = Album_Metadata {
-        slug: album_request.slug.clone(),
+        slug: slug.clone(),
         title: album_request.title,
         description: album_request.description,
         short_title: album_request.short_title,
-        date: album_request.date,
+        date,
         camera: album_request.camera,
         lens: album_request.lens,
         phone: album_request.phone,
         preview_img_one_url: album_request.preview_img_one_url,
+        raw_preview_url: None,
         featured: album_request.featured,
         category: album_request.category,
+        views: 0,
+        created_at: None,
+        updated_at: None,
     };
 
+    // The album row and all its content are inserted in one transaction, dropping
+    // `tx` without committing rolls everything back
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        ApiError::internal("Failed to create album")
+    })?;
+
+    // A concurrent create of the same slug blocks here until this one commits, then
+    // fails on the primary key before it can touch the album directory
+    if let Err(e) = database::create_album(&mut *tx, &album).await {
+        return Err(create_album_error(&state, &slug, e).await);
+    }
+
     // Create album directory
-    let album_dir = state.upload_dir.join(&album_request.slug);
+    let album_dir = state.config.upload_dir.join(&slug);
+    let created_dir = !album_dir.exists();
     fs::create_dir_all(&album_dir).await.map_err(|e| {
         error!("Failed to create album directory {}: {}", album_dir.display(), e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        ApiError::internal("Failed to create album directory")
     })?;
 
-    // Create album in database
-    if let Err(e) = database::create_album(&state.db, &album).await {
-        error!("Failed to create album: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
     // Process uploaded files
-    let mut added_photos = Vec::new();
-    
-    for (filename, data) in file_data {
-        // Generate unique filename
-        let ext = std::path::Path::new(&filename)
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-        
-        let file_stem = std::path::Path::new(&filename)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("file");
-            
-        let unique_filename = format!("{}_{}.{}", 
-            file_stem,
-            Uuid::new_v4().to_string()[..8].to_string(),
-            ext
-        );
-
-        let file_path = album_dir.join(&unique_filename);
-        
-        // Write file
-        let mut file = fs::File::create(&file_path).await.map_err(|e| {
-            error!("Failed to create file {}: {}", file_path.display(), e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        
-        file.write_all(&data).await.map_err(|e| {
-            error!("Failed to write file {}: {}", file_path.display(), e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        // Generate thumbnail if it's an image
-        if is_image(&filename) {
-            generate_thumbnail(&file_path, &data).await;
-        }
-
-        let img_url = format!("/files/{}/{}", album_request.slug, unique_filename);
+    let mut written_files = Vec::new();
+    let result = store_album_files(
+        &state,
+        &mut tx,
+        &slug,
+        staged_files,
+        file_captions,
+        cover_file.as_deref(),
+        &mut written_files,
+    )
+    .await;
 
-        // Add to album content
-        let content = Album_Content {
-            slug: album_request.slug.clone(),
-            img_url: img_url.clone(),
-            caption: format!("Photo from {}", filename),
-        };
-
-        if let Err(e) = database::add_album_content(&state.db, &content).await {
-            error!("Failed to add album content: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    let response = match result {
+        Ok(stored) => stored,
+        Err(e) => {
+            remove_written_files(&album_dir, created_dir, &written_files, &state.config.thumbnail_sizes).await;
+            return Err(e);
         }
+    };
 
-        added_photos.push(content);
-        info!("Added photo: {} to album {}", unique_filename, album_request.slug);
+    if let Err(e) = tx.commit().await {
+        error!("Failed to commit album {}: {}", slug, e);
+        remove_written_files(&album_dir, created_dir, &written_files, &state.config.thumbnail_sizes).await;
+        return Err(ApiError::internal("Failed to create album"));
     }
 
-    Ok(Json(AddPhotosResponse {
-        message: "Album created with files successfully".to_string(),
-        album_slug: album_request.slug,
-        added_photos,
-    }))
+    Ok((StatusCode::CREATED, created_at("/albums", &slug), Json(response)))
 }
 
 /// Update an existing album
 ///
-/// Update an existing photo album. Only provided fields will be updated.
+/// Update an existing photo album. Only provided fields will be updated; `camera`, `lens`
+/// and `phone` are cleared when sent as an explicit `null`.
+///
+/// Send the `ETag` of `GET /albums/{slug}` in `If-Match` to only update the album if nobody
+/// changed it since: otherwise the update is refused with `412 Precondition Failed`. Without
+/// `If-Match` the album is updated whatever its version. The response carries the new `ETag`.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     put,
     path = "/albums/{slug}",
     request_body = UpdateAlbumRequest,
     responses(
-        (status = 200, description = "Album updated successfully", body = AlbumOperationResponse),
-        (status = 400, description = "Invalid request data"),
-        (status = 404, description = "Album not found"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Album updated successfully", body = AlbumOperationResponse,
+            headers(("ETag" = String, description = "New version of the album"))),
+        (status = 400, description = "Invalid request data", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 412, description = "The album changed since the version in If-Match", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
-        ("slug" = String, Path, description = "Album slug identifier")
+        ("slug" = String, Path, description = "Album slug identifier"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the album version the update is based on")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Photo Albums"
 )]
 pub async fn update_album(
     State(state): State<AppState>,
     Path(slug): Path<String>,
+    headers: HeaderMap,
     Json(request): Json<UpdateAlbumRequest>,
-) -> Result<Json<AlbumOperationResponse>, StatusCode> {
-    // Get existing album
+) -> Result<([(HeaderName, HeaderValue); 1], Json<AlbumOperationResponse>), ApiError> {
+    // With `If-Match`, the update below only applies to the version the client has seen
+    let expected_version = if headers.contains_key(IF_MATCH) {
+        let version = match database::album_version(&state.db, &slug).await {
+            Ok(Some(version)) => version,
+            Ok(None) => return Err(album_not_found(&slug)),
+            Err(e) => {
+                error!("Failed to fetch album version: {}", e);
+                return Err(ApiError::internal("Failed to fetch existing album"));
+            }
+        };
+        if !if_match(&headers, &album_etag(version)) {
+            return Err(album_changed(&slug));
+        }
+        Some(version)
+    } else {
+        None
+    };
+
+    // Get existing album
     let mut existing_album = match database::get_album_with_content(&state.db, &slug).await {
         Ok(Some(album)) => album.metadata,
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Ok(None) => return Err(album_not_found(&slug)),
         Err(e) => {
             error!("Failed to fetch existing album: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("Failed to fetch existing album"));
         }
     };
 
@@ -366,18 +1047,19 @@ pub async fn update_album(
         existing_album.short_title = short_title;
     }
     if let Some(date) = request.date {
-        existing_album.date = date;
+        existing_album.date = parse_date("date", &date)?.to_string();
     }
     if let Some(camera) = request.camera {
-        existing_album.camera = Some(camera);
+        existing_album.camera = camera;
     }
     if let Some(lens) = request.lens {
-        existing_album.lens = Some(lens);
+        existing_album.lens = lens;
     }
     if let Some(phone) = request.phone {
-        existing_album.phone = Some(phone);
+        existing_album.phone = phone;
     }
     if let Some(preview_img_one_url) = request.preview_img_one_url {
+        validate_cover_url(&state, &preview_img_one_url).await?;
         existing_album.preview_img_one_url = preview_img_one_url;
     }
     if let Some(featured) = request.featured {
@@ -387,57 +1069,301 @@ pub async fn update_album(
         existing_album.category = category;
     }
 
-    match database::update_album(&state.db, &slug, &existing_album).await {
-        Ok(true) => Ok(Json(AlbumOperationResponse {
-            message: "Album updated successfully".to_string(),
-            slug,
-        })),
-        Ok(false) => Err(StatusCode::NOT_FOUND),
+    match database::update_album(&state.db, &slug, &existing_album, expected_version).await {
+        Ok(Some(version)) => Ok((
+            [(ETAG, album_etag(version))],
+            Json(AlbumOperationResponse {
+                message: "Album updated successfully".to_string(),
+                slug,
+            }),
+        )),
+        // Changed (or trashed) between the version check and the update
+        Ok(None) if expected_version.is_some() => Err(album_changed(&slug)),
+        Ok(None) => Err(album_not_found(&slug)),
         Err(e) => {
             error!("Failed to update album: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to update album"))
+        }
+    }
+}
+
+/// Rename an album
+///
+/// Change the slug of an album. The album row, its content and their `/files/{slug}/` URLs
+/// are updated in one transaction, and the upload directory is renamed with them.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/albums/{slug}/rename",
+    request_body = RenameRequest,
+    responses(
+        (status = 200, description = "Album renamed, the response holds the new slug", body = AlbumOperationResponse),
+        (status = 400, description = "Invalid new slug", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 409, description = "An album or upload folder already uses the new slug", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Current album slug")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn rename_album(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<RenameRequest>,
+) -> Result<Json<AlbumOperationResponse>, ApiError> {
+    let new_slug = request.new_slug;
+    validate_slug(&new_slug)?;
+    if new_slug == slug {
+        return Err(ApiError::bad_request("SAME_SLUG", format!("Album is already named '{}'", slug)));
+    }
+    if album_slug_taken(&state, &new_slug).await? {
+        return Err(album_already_exists(&new_slug));
+    }
+
+    let old_dir = state.config.upload_dir.join(&slug);
+    let new_dir = state.config.upload_dir.join(&new_slug);
+    if new_dir.exists() {
+        return Err(ApiError::conflict(
+            "FOLDER_ALREADY_EXISTS",
+            format!("Upload folder '{}' already exists", new_slug),
+        ));
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        ApiError::internal("Failed to rename album")
+    })?;
+
+    match database::rename_album(&mut tx, &slug, &new_slug).await {
+        Ok(true) => {}
+        Ok(false) => return Err(album_not_found(&slug)),
+        Err(e) if database::is_unique_violation(&e) => return Err(album_already_exists(&new_slug)),
+        Err(e) => {
+            error!("Failed to rename album: {}", e);
+            return Err(ApiError::internal("Failed to rename album"));
+        }
+    }
+
+    // Rename the directory while the rows are still locked, so a failure rolls everything back
+    let moved_dir = old_dir.exists();
+    if moved_dir {
+        if let Err(e) = fs::rename(&old_dir, &new_dir).await {
+            error!("Failed to rename {} to {}: {}", old_dir.display(), new_dir.display(), e);
+            return Err(ApiError::internal("Failed to rename album directory"));
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        error!("Failed to commit rename of album {}: {}", slug, e);
+        if moved_dir {
+            if let Err(e) = fs::rename(&new_dir, &old_dir).await {
+                error!("Failed to move {} back to {}: {}", new_dir.display(), old_dir.display(), e);
+            }
         }
+        return Err(ApiError::internal("Failed to rename album"));
     }
+
+    // Resized copies are cached by path, the old ones are no longer reachable
+    let _ = fs::remove_dir_all(state.config.upload_dir.join(RESIZE_CACHE_DIR).join(&slug)).await;
+
+    info!("Renamed album {} to {}", slug, new_slug);
+    Ok(Json(AlbumOperationResponse {
+        message: format!("Album renamed from '{}' to '{}'", slug, new_slug),
+        slug: new_slug,
+    }))
 }
 
 /// Delete an album
 ///
-/// Delete an existing photo album and all its content from the database.
-/// Note: This only removes database entries, not the actual files from the server.
+/// Moves the album to the trash: it disappears from every listing with its content, keeps
+/// its slug and files, and can be brought back with `POST /albums/{slug}/restore`.
+/// With `hard=true` the album (in the trash or not) and its content are deleted for good,
+/// with the files, thumbnails and WebP copies no other album uses.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid admin API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     delete,
     path = "/albums/{slug}",
     responses(
         (status = 200, description = "Album deleted successfully", body = AlbumOperationResponse),
-        (status = 404, description = "Album not found"),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 403, description = "Forbidden - requires an admin API key", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
-        ("slug" = String, Path, description = "Album slug identifier")
+        ("slug" = String, Path, description = "Album slug identifier"),
+        DeleteQuery
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Photo Albums"
 )]
 pub async fn delete_album(
+    _admin: RequireAdmin,
     State(state): State<AppState>,
+    Extension(ApiKeyLabel(key_label)): Extension<ApiKeyLabel>,
     Path(slug): Path<String>,
-) -> Result<Json<AlbumOperationResponse>, StatusCode> {
+    Query(query): Query<DeleteQuery>,
+) -> Result<Json<AlbumOperationResponse>, ApiError> {
+    if query.hard {
+        return purge_album(&state, slug, &key_label).await;
+    }
+
     match database::delete_album(&state.db, &slug).await {
         Ok(true) => {
-            info!("Deleted album: {}", slug);
+            info!("Moved album {} to the trash (API key: {})", slug, key_label);
             Ok(Json(AlbumOperationResponse {
-                message: "Album deleted successfully".to_string(),
+                message: "Album moved to the trash".to_string(),
                 slug,
             }))
         }
-        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Ok(false) => Err(album_not_found(&slug)),
         Err(e) => {
             error!("Failed to delete album: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to delete album"))
+        }
+    }
+}
+
+/// `DELETE /albums/{slug}?hard=true`
+async fn purge_album(state: &AppState, slug: String, key_label: &str) -> Result<Json<AlbumOperationResponse>, ApiError> {
+    match purge_album_and_files(state, &slug).await {
+        Ok(Some(photos)) => {
+            info!("Permanently deleted album {} and {} photos (API key: {})", slug, photos, key_label);
+            Ok(Json(AlbumOperationResponse {
+                message: "Album deleted permanently".to_string(),
+                slug,
+            }))
+        }
+        Ok(None) => Err(album_not_found(&slug)),
+        Err(e) => {
+            error!("Failed to delete album: {}", e);
+            Err(ApiError::internal("Failed to delete album"))
+        }
+    }
+}
+
+/// Delete an album for good, with its files and its upload folder when nothing else is left in it
+///
+/// Returns the number of photos deleted, `None` if there is no such album.
+async fn purge_album_and_files(state: &AppState, slug: &str) -> Result<Option<usize>, sqlx::Error> {
+    let Some(img_urls) = database::purge_album(&state.db, slug).await? else {
+        return Ok(None);
+    };
+
+    remove_photo_files(state, &img_urls).await;
+    let _ = fs::remove_dir_all(state.config.upload_dir.join(RESIZE_CACHE_DIR).join(slug)).await;
+    // Fails, keeping the folder, when files other than the album's are left in it
+    let _ = fs::remove_dir(state.config.upload_dir.join(slug)).await;
+
+    Ok(Some(img_urls.len()))
+}
+
+/// Delete several albums
+///
+/// Permanently deletes each listed album (in the trash or not) with its content and files,
+/// like `DELETE /albums/{slug}?hard=true`. Each album is deleted in its own transaction, so a
+/// failure only leaves that album in place; the response reports the outcome of every slug.
+/// 
+/// **Authentication Required**: This endpoint requires a valid admin API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/albums/batch-delete",
+    request_body = BatchDeleteRequest,
+    responses(
+        (status = 200, description = "Per-album results", body = BatchDeleteResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 403, description = "Forbidden - requires an admin API key", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn batch_delete_albums(
+    _admin: RequireAdmin,
+    State(state): State<AppState>,
+    Extension(ApiKeyLabel(key_label)): Extension<ApiKeyLabel>,
+    Json(request): Json<BatchDeleteRequest>,
+) -> Json<BatchDeleteResponse> {
+    let mut results = Vec::with_capacity(request.slugs.len());
+    for slug in request.slugs {
+        let status = match purge_album_and_files(&state, &slug).await {
+            Ok(Some(photos)) => {
+                info!("Permanently deleted album {} and {} photos (API key: {})", slug, photos, key_label);
+                BatchDeleteStatus::Deleted
+            }
+            Ok(None) => BatchDeleteStatus::NotFound,
+            Err(e) => {
+                error!("Failed to delete album {}: {}", slug, e);
+                BatchDeleteStatus::Error
+            }
+        };
+        results.push(BatchDeleteResult { slug, status });
+    }
+
+    let deleted = results.iter().filter(|result| result.status == BatchDeleteStatus::Deleted).count();
+    Json(BatchDeleteResponse {
+        message: format!("Deleted {} of {} albums", deleted, results.len()),
+        results,
+    })
+}
+
+/// Restore a deleted album
+///
+/// Brings an album moved to the trash by `DELETE /albums/{slug}` back, with its content.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/albums/{slug}/restore",
+    responses(
+        (status = 200, description = "Album restored", body = AlbumOperationResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "No deleted album with this slug", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn restore_album(
+    State(state): State<AppState>,
+    Extension(ApiKeyLabel(key_label)): Extension<ApiKeyLabel>,
+    Path(slug): Path<String>,
+) -> Result<Json<AlbumOperationResponse>, ApiError> {
+    match database::restore_album(&state.db, &slug).await {
+        Ok(true) => {
+            info!("Restored album {} (API key: {})", slug, key_label);
+            Ok(Json(AlbumOperationResponse {
+                message: "Album restored successfully".to_string(),
+                slug,
+            }))
+        }
+        Ok(false) => Err(ApiError::not_found(
+            "DELETED_ALBUM_NOT_FOUND",
+            format!("No deleted album '{}'", slug),
+        )),
+        Err(e) => {
+            error!("Failed to restore album: {}", e);
+            Err(ApiError::internal("Failed to restore album"))
         }
     }
 }
@@ -445,11 +1371,18 @@ pub async fn delete_album(
 /// Add photos to an existing album
 ///
 /// Upload and add new photos to an existing album
+///
+/// Files are added one by one: a file that cannot be stored does not undo the others. The
+/// response lists what was added and, in `failed`, each file that was not with the reason,
+/// with a `207 Multi-Status` when `failed` is not empty. A batch that would take the album
+/// past `MAX_PHOTOS_PER_ALBUM` photos is rejected with `409 Conflict`, and none of its files
+/// reach the album directory.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 /// 
 /// Required form fields:
 /// - `caption`: Optional caption for the photos
+/// - `captions`: Optional per-file captions, a JSON array (upload order) or object (filename → caption)
 /// - `files`: Files to upload (binary, can be multiple files)
 #[utoipa::path(
     put,
@@ -460,16 +1393,22 @@ pub async fn delete_album(
     ),
     responses(
         (status = 200, description = "Photos added successfully", body = AddPhotosResponse),
-        (status = 400, description = "Bad request - no files uploaded"),
-        (status = 404, description = "Album not found"),
-        (status = 401, description = "Unauthorized - invalid or missing API key"),
-        (status = 500, description = "Internal server error")
+        (status = 207, description = "Some files could not be added, see `failed`", body = AddPhotosResponse),
+        (status = 400, description = "Bad request - no files uploaded", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 409, description = "The album would hold more than `MAX_PHOTOS_PER_ALBUM` photos", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 413, description = "Payload too large - a file or the request exceeds the upload size limit", body = ErrorResponse),
+        (status = 415, description = "Unsupported media type - file content is not in the allowlist", body = ErrorResponse),
+        (status = 429, description = "Too many upload requests from this IP", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
         ("slug" = String, Path, description = "Album slug identifier")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Photo Albums"
 )]
@@ -477,189 +1416,1351 @@ pub async fn add_photos_to_album(
     State(state): State<AppState>,
     Path(slug): Path<String>,
     mut multipart: Multipart,
-) -> Result<Json<AddPhotosResponse>, StatusCode> {
+) -> Result<(StatusCode, Json<AddPhotosResponse>), ApiError> {
     // Check if album exists
     if !database::album_exists(&state.db, &slug).await.map_err(|e| {
         error!("Failed to check album existence: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        ApiError::internal("Failed to check album existence")
     })? {
-        return Err(StatusCode::NOT_FOUND);
+        return Err(album_not_found(&slug));
     }
 
+    let staging = StagingDir::create(&state.config.upload_dir).await?;
     let mut caption: Option<String> = None;
-    let mut file_data: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut captions: Option<PhotoCaptions> = None;
+    let mut staged_files: Vec<StagedFile> = Vec::new();
 
-    // Collect all fields
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error!("Failed to read multipart field: {}", e);
-        StatusCode::BAD_REQUEST
-    })? {
+    // Stream files to the staging directory and collect the other fields
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
         let name = field.name().unwrap_or("");
 
         if name == "caption" {
             let data = field.bytes().await.map_err(|e| {
                 error!("Failed to read caption data: {}", e);
-                StatusCode::BAD_REQUEST
+                ApiError::bad_request("INVALID_MULTIPART", "Failed to read caption field")
             })?;
             caption = Some(String::from_utf8(data.to_vec()).map_err(|e| {
                 error!("Invalid UTF-8 in caption: {}", e);
-                StatusCode::BAD_REQUEST
+                ApiError::bad_request("INVALID_CAPTION", "caption must be valid UTF-8")
             })?);
+        } else if name == "captions" {
+            captions = Some(read_captions_field(field).await?);
         } else if name == "files" {
             let filename = field.file_name().unwrap_or("unknown").to_string();
-            let data = field.bytes().await.map_err(|e| {
-                error!("Failed to read file data: {}", e);
-                StatusCode::BAD_REQUEST
-            })?;
-            file_data.push((filename, data.to_vec()));
+            let staged = staging
+                .stage_field(field, filename, state.config.max_upload_bytes, &state.config.allowed_mime_types)
+                .await?;
+            info!("Received file: {} ({}, {} bytes)", staged.filename, staged.mime_type, staged.size);
+            staged_files.push(staged);
         }
     }
 
-    if file_data.is_empty() {
+    if staged_files.is_empty() {
         error!("No files provided");
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(ApiError::bad_request("NO_FILES", "At least one file must be provided in the files field"));
     }
 
+    let file_captions = match captions {
+        Some(captions) => resolve_captions(captions, &staged_files)?,
+        None => vec![None; staged_files.len()],
+    };
+
     // Get album directory
-    let album_dir = state.upload_dir.join(&slug);
+    let album_dir = state.config.upload_dir.join(&slug);
     fs::create_dir_all(&album_dir).await.map_err(|e| {
         error!("Failed to create album directory {}: {}", album_dir.display(), e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        ApiError::internal("Failed to create album directory")
     })?;
 
     let mut added_photos = Vec::new();
+    let mut skipped_duplicates = Vec::new();
+    let mut failed = Vec::new();
+    let mut recompressed = Vec::new();
     let default_caption = caption.unwrap_or_else(|| "Photo".to_string());
 
-    for (filename, data) in file_data {
-        // Generate unique filename
-        let ext = std::path::Path::new(&filename)
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-        
-        let file_stem = std::path::Path::new(&filename)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("file");
-            
-        let unique_filename = format!("{}_{}.{}", 
-            file_stem,
-            Uuid::new_v4().to_string()[..8].to_string(),
-            ext
-        );
-
-        let file_path = album_dir.join(&unique_filename);
-        
-        // Write file
-        let mut file = fs::File::create(&file_path).await.map_err(|e| {
-            error!("Failed to create file {}: {}", file_path.display(), e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        
-        file.write_all(&data).await.map_err(|e| {
-            error!("Failed to write file {}: {}", file_path.display(), e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let photo_count = database::album_photo_count(&state.db, &slug).await.map_err(|e| {
+        error!("Failed to count photos of album {}: {}", slug, e);
+        ApiError::internal("Failed to add photos")
+    })?;
+
+    let mut duplicates = Vec::with_capacity(staged_files.len());
+    for staged in &staged_files {
+        duplicates.push(duplicate_photo(&state, &state.db, &slug, staged).await?);
+    }
+
+    // Files already in the album are skipped, they do not count. Checked again below once
+    // the album is locked, this only saves processing a batch that cannot fit
+    let incoming = duplicates.iter().filter(|duplicate| duplicate.is_none()).count();
+    let max_photos = state.config.max_photos_per_album;
+    if usize::try_from(photo_count).unwrap_or_default() + incoming > max_photos {
+        return Err(album_full(&slug, photo_count, incoming, max_photos));
+    }
 
-        // Generate thumbnail if it's an image
-        if is_image(&filename) {
-            generate_thumbnail(&file_path, &data).await;
+    // Files are processed in the staging directory, not to hold the album lock (and a
+    // connection) meanwhile, and only moved to the album once they are known to fit
+    let processing_dir = staging.path().join("processed");
+    fs::create_dir_all(&processing_dir).await.map_err(|e| {
+        error!("Failed to create directory {}: {}", processing_dir.display(), e);
+        ApiError::internal("Failed to prepare upload")
+    })?;
+    let mut uploads = process_uploads(&state, &slug, &processing_dir, staged_files, duplicates).await;
+
+    // The files are moved and the rows added with the album locked, so concurrent batches
+    // are counted one after the other
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        ApiError::internal("Failed to add photos")
+    })?;
+    let incoming = uploads.iter().filter(|upload| matches!(upload, ProcessedUpload::Stored(_))).count();
+    match database::lock_album_photo_count(&mut tx, &slug).await {
+        Ok(Some(count)) if usize::try_from(count).unwrap_or_default() + incoming > max_photos => {
+            return Err(album_full(&slug, count, incoming, max_photos));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(album_not_found(&slug)),
+        Err(e) => {
+            error!("Failed to count photos of album {}: {}", slug, e);
+            return Err(ApiError::internal("Failed to add photos"));
         }
+    }
 
-        let img_url = format!("/files/{}/{}", slug, unique_filename);
+    let mut written = Vec::new();
+    for upload in &mut uploads {
+        let ProcessedUpload::Stored(stored) = upload else {
+            continue;
+        };
+        match publish_upload(stored, &album_dir, &state).await {
+            Ok(()) if !stored.stored.reused => written.push(stored.stored.path.clone()),
+            Ok(()) => {}
+            Err(e) => {
+                error!("Failed to move {} to album {}: {}", stored.stored.path.display(), slug, e);
+                *upload = ProcessedUpload::Failed(FailedUpload {
+                    filename: stored.stored.filename.clone(),
+                    code: "INTERNAL_ERROR".to_string(),
+                    error: "Failed to store file".to_string(),
+                });
+            }
+        }
+    }
 
-        // Add to album content
-        let content = Album_Content {
-            slug: slug.clone(),
-            img_url: img_url.clone(),
-            caption: default_caption.clone(),
+    for (upload, caption) in uploads.into_iter().zip(file_captions) {
+        let upload = match upload {
+            ProcessedUpload::Stored(upload) => upload,
+            ProcessedUpload::Duplicate(existing) => {
+                skipped_duplicates.push(existing);
+                continue;
+            }
+            ProcessedUpload::Failed(failure) => {
+                failed.push(failure);
+                continue;
+            }
         };
 
-        if let Err(e) = database::add_album_content(&state.db, &content).await {
-            error!("Failed to add album content: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        let caption = caption.unwrap_or_else(|| default_caption.clone());
+        let filename = upload.stored.filename.clone();
+        let written = (!upload.stored.reused).then(|| upload.stored.path.clone());
+        let sizes = upload.recompressed();
+        let mut content = upload.into_content(&state, &slug, caption);
+
+        // Without its row the file would be an orphan, so it goes and the next file is tried
+        match add_content_in(&mut tx, &content).await {
+            Ok(created_at) => content.created_at = created_at,
+            Err(e) => {
+                error!("Failed to add album content for {}: {}", filename, e);
+                remove_written_files(&album_dir, false, written.as_slice(), &state.config.thumbnail_sizes).await;
+                failed.push(FailedUpload {
+                    filename,
+                    code: "INTERNAL_ERROR".to_string(),
+                    error: "Failed to add album content".to_string(),
+                });
+                continue;
+            }
         }
 
+        info!("Added photo: {} to album {}", content.img_url, slug);
         added_photos.push(content);
-        info!("Added photo: {} to album {}", unique_filename, slug);
+        recompressed.extend(sizes);
     }
 
-    Ok(Json(AddPhotosResponse {
-        message: "Photos added successfully".to_string(),
-        album_slug: slug,
-        added_photos,
-    }))
+    if let Err(e) = tx.commit().await {
+        error!("Failed to commit photos of album {}: {}", slug, e);
+        remove_written_files(&album_dir, false, &written, &state.config.thumbnail_sizes).await;
+        return Err(ApiError::internal("Failed to add photos"));
+    }
+
+    let status = if failed.is_empty() { StatusCode::OK } else { StatusCode::MULTI_STATUS };
+
+    let message = if failed.is_empty() {
+        "Photos added successfully".to_string()
+    } else {
+        format!("Added {} of {} photos", added_photos.len(), added_photos.len() + skipped_duplicates.len() + failed.len())
+    };
+
+    Ok((
+        status,
+        Json(AddPhotosResponse {
+            message,
+            album_slug: slug,
+            added_photos,
+            skipped_duplicates,
+            failed,
+            recompressed,
+        }),
+    ))
 }
 
-/// Remove a photo from an album
+/// Remove photos from an album
 ///
-/// Remove a specific photo from an album. Only removes the database entry, not the actual file.
+/// Remove one photo (`{ "img_url": "..." }`) or several at once (`{ "img_urls": [...] }`) from
+/// an album in a single transaction. The files (and their thumbnails and WebP copies) are
+/// deleted too, unless another album still uses them. With `img_urls`, URLs that are not
+/// photos of the album are reported in `not_found`; a single missing `img_url` is a `404`.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid admin API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     delete,
     path = "/albums/{slug}/photos",
     request_body = RemovePhotoRequest,
     responses(
-        (status = 200, description = "Photo removed successfully", body = AlbumOperationResponse),
-        (status = 404, description = "Album or photo not found"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Photos removed", body = RemovePhotosResponse),
+        (status = 400, description = "No photo given", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 403, description = "Forbidden - requires an admin API key", body = ErrorResponse),
+        (status = 404, description = "Album or photo not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
         ("slug" = String, Path, description = "Album slug identifier")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Photo Albums"
 )]
 pub async fn remove_photo_from_album(
+    _admin: RequireAdmin,
     State(state): State<AppState>,
     Path(slug): Path<String>,
     Json(request): Json<RemovePhotoRequest>,
-) -> Result<Json<AlbumOperationResponse>, StatusCode> {
-    match database::remove_album_content(&state.db, &slug, &request.img_url).await {
+) -> Result<Json<RemovePhotosResponse>, ApiError> {
+    let (single, mut img_urls) = match request {
+        RemovePhotoRequest::One { img_url } => (true, vec![img_url]),
+        RemovePhotoRequest::Many { img_urls } => (false, img_urls),
+    };
+    img_urls.sort();
+    img_urls.dedup();
+    if img_urls.is_empty() {
+        return Err(ApiError::bad_request("NO_PHOTOS", "img_urls must list at least one photo"));
+    }
+
+    if !database::album_exists(&state.db, &slug).await.map_err(|e| {
+        error!("Failed to check album existence: {}", e);
+        ApiError::internal("Failed to check album existence")
+    })? {
+        return Err(album_not_found(&slug));
+    }
+
+    let removed = database::remove_album_contents(&state.db, &slug, &img_urls).await.map_err(|e| {
+        error!("Failed to remove photos from album: {}", e);
+        ApiError::internal("Failed to remove photo from album")
+    })?;
+    if single && removed.is_empty() {
+        return Err(photo_not_found(&slug, &img_urls[0]));
+    }
+
+    remove_photo_files(&state, &removed).await;
+    info!("Removed {} of {} photos from album {}", removed.len(), img_urls.len(), slug);
+
+    let not_found = img_urls.iter().filter(|url| !removed.contains(url)).cloned().collect();
+    Ok(Json(RemovePhotosResponse {
+        message: format!("Removed {} of {} photos", removed.len(), img_urls.len()),
+        slug,
+        requested: img_urls.len(),
+        removed: removed.len(),
+        not_found,
+    }))
+}
+
+/// Delete the files of removed photos, with the thumbnails and copies made from them, unless an
+/// album still references them
+async fn remove_photo_files(state: &AppState, img_urls: &[String]) {
+    let still_used = match database::referenced_file_urls(&state.db, img_urls).await {
+        Ok(still_used) => still_used,
+        Err(e) => {
+            error!("Failed to check whether removed photos are still used, keeping their files: {}", e);
+            return;
+        }
+    };
+
+    for img_url in img_urls.iter().filter(|url| !still_used.contains(url)) {
+        let Some(path) = file_path(&state.config.upload_dir, img_url) else {
+            continue;
+        };
+        let thumbnails = state.config.thumbnail_sizes.iter().map(|&size| thumbnail_variant_path(&path, size));
+        let generated = [thumbnail_path(&path), webp_path(&path)].into_iter().chain(precompressed_paths(&path));
+        for path in std::iter::once(path.clone()).chain(generated).chain(thumbnails) {
+            if let Err(e) = fs::remove_file(&path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to delete {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Reorder the photos of an album
+///
+/// Set the display order of an album's photos. The request must list every photo
+/// of the album exactly once; positions are rewritten in a single transaction.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    put,
+    path = "/albums/{slug}/photos/reorder",
+    request_body = ReorderPhotosRequest,
+    responses(
+        (status = 200, description = "Photos reordered successfully", body = AlbumOperationResponse),
+        (status = 400, description = "The list does not match the album's photos", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn reorder_album_photos(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<ReorderPhotosRequest>,
+) -> Result<Json<AlbumOperationResponse>, ApiError> {
+    let album = match database::get_album_with_content(&state.db, &slug).await {
+        Ok(Some(album)) => album,
+        Ok(None) => return Err(album_not_found(&slug)),
+        Err(e) => {
+            error!("Failed to fetch album: {}", e);
+            return Err(ApiError::internal("Failed to fetch album"));
+        }
+    };
+
+    // The new order must be a permutation of the album's current content
+    let mut current: Vec<&str> = album.content.iter().map(|c| c.img_url.as_str()).collect();
+    let mut requested: Vec<&str> = request.img_urls.iter().map(String::as_str).collect();
+    current.sort_unstable();
+    requested.sort_unstable();
+
+    if current != requested {
+        return Err(invalid_photo_order(&slug));
+    }
+
+    match database::reorder_album_content(&state.db, &slug, &request.img_urls).await {
+        Ok(true) => {
+            info!("Reordered {} photos in album {}", request.img_urls.len(), slug);
+            Ok(Json(AlbumOperationResponse {
+                message: "Photos reordered successfully".to_string(),
+                slug,
+            }))
+        }
+        // The album content changed since it was fetched
+        Ok(false) => Err(invalid_photo_order(&slug)),
+        Err(e) => {
+            error!("Failed to reorder album photos: {}", e);
+            Err(ApiError::internal("Failed to reorder album photos"))
+        }
+    }
+}
+
+/// Move a photo to another album
+///
+/// Move one of the album's photos to the end of the `target_slug` album. Its file, thumbnails
+/// and WebP copy are moved to the target folder (renamed if the name is taken there) and its
+/// URL is updated, along with any album cover using it. Files are moved back if the database
+/// update fails.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/albums/{slug}/photos/move",
+    request_body = MovePhotoRequest,
+    responses(
+        (status = 200, description = "Photo moved", body = MovePhotoResponse),
+        (status = 400, description = "Source and target are the same album", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Album or photo not found", body = ErrorResponse),
+        (status = 409, description = "The target album already has a photo with this URL", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Slug of the album holding the photo")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn move_photo(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<MovePhotoRequest>,
+) -> Result<Json<MovePhotoResponse>, ApiError> {
+    let MovePhotoRequest { img_url, target_slug } = request;
+    if target_slug == slug {
+        return Err(ApiError::bad_request("SAME_ALBUM", format!("Photo is already in album '{}'", slug)));
+    }
+    for album in [&slug, &target_slug] {
+        if !database::album_exists(&state.db, album).await.map_err(|e| {
+            error!("Failed to check album existence: {}", e);
+            ApiError::internal("Failed to check album existence")
+        })? {
+            return Err(album_not_found(album));
+        }
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        ApiError::internal("Failed to move photo")
+    })?;
+
+    let photo = match database::lock_album_content(&mut *tx, &slug, &img_url).await {
+        Ok(Some(photo)) => photo,
+        Ok(None) => return Err(photo_not_found(&slug, &img_url)),
+        Err(e) => {
+            error!("Failed to fetch photo: {}", e);
+            return Err(ApiError::internal("Failed to move photo"));
+        }
+    };
+
+    // Photos stored outside the album folder (external URLs) only change album
+    let source_dir = state.config.upload_dir.join(&slug);
+    let source = file_path(&state.config.upload_dir, &img_url)
+        .filter(|path| path.parent() == Some(source_dir.as_path()) && path.is_file());
+    let (new_img_url, new_webp_url, moved) = match source {
+        Some(source) => {
+            let target_dir = state.config.upload_dir.join(&target_slug);
+            fs::create_dir_all(&target_dir).await.map_err(|e| {
+                error!("Failed to create album directory {}: {}", target_dir.display(), e);
+                ApiError::internal("Failed to create album directory")
+            })?;
+
+            let file_name = source.file_name().unwrap_or_default().to_string_lossy();
+            let target = target_dir.join(free_file_name(&target_dir, &file_name, &state.config.thumbnail_sizes));
+            let moved = move_photo_files(&source, &target, &state.config.thumbnail_sizes).await.map_err(|e| {
+                error!("Failed to move {} to {}: {}", source.display(), target.display(), e);
+                ApiError::internal("Failed to move photo file")
+            })?;
+
+            let new_img_url = format!(
+                "/files/{}/{}",
+                target_slug,
+                target.file_name().unwrap_or_default().to_string_lossy()
+            );
+            let new_webp_url = photo
+                .webp_url
+                .as_ref()
+                .map(|_| webp_path(std::path::Path::new(&new_img_url)).to_string_lossy().into_owned());
+            (new_img_url, new_webp_url, moved)
+        }
+        None => (img_url.clone(), photo.webp_url.clone(), Vec::new()),
+    };
+
+    let result = match database::move_album_content(
+        &mut tx,
+        &slug,
+        &img_url,
+        &target_slug,
+        &new_img_url,
+        new_webp_url.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => tx.commit().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        restore_files(&moved).await;
+        if database::is_unique_violation(&e) {
+            return Err(ApiError::conflict(
+                "PHOTO_ALREADY_EXISTS",
+                format!("Album '{}' already has photo '{}'", target_slug, new_img_url),
+            ));
+        }
+        error!("Failed to move photo {} to album {}: {}", img_url, target_slug, e);
+        return Err(ApiError::internal("Failed to move photo"));
+    }
+
+    info!("Moved photo {} from album {} to {}", img_url, slug, target_slug);
+    Ok(Json(MovePhotoResponse {
+        message: format!("Photo moved to album '{}'", target_slug),
+        slug: target_slug,
+        img_url: new_img_url,
+    }))
+}
+
+/// Tag a photo
+///
+/// Add `tags` to one of the album's photos. Tags it already has are ignored.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    put,
+    path = "/albums/{slug}/photos/tags",
+    request_body = PhotoTagsRequest,
+    responses(
+        (status = 200, description = "Tags added, with all tags of the photo", body = PhotoTagsResponse),
+        (status = 400, description = "No tags or an invalid tag", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Photo not found in the album", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn add_photo_tags(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<PhotoTagsRequest>,
+) -> Result<Json<PhotoTagsResponse>, ApiError> {
+    let tags = normalize_tags(request.tags)?;
+
+    match database::add_photo_tags(&state.db, &slug, &request.img_url, &tags).await {
+        Ok(Some(tags)) => Ok(Json(PhotoTagsResponse {
+            slug,
+            img_url: request.img_url,
+            tags,
+        })),
+        Ok(None) => Err(photo_not_found(&slug, &request.img_url)),
+        Err(e) => {
+            error!("Failed to add photo tags: {}", e);
+            Err(ApiError::internal("Failed to add photo tags"))
+        }
+    }
+}
+
+/// Untag a photo
+///
+/// Remove `tags` from one of the album's photos. Tags it does not have are ignored.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    delete,
+    path = "/albums/{slug}/photos/tags",
+    request_body = PhotoTagsRequest,
+    responses(
+        (status = 200, description = "Tags removed, with the remaining tags of the photo", body = PhotoTagsResponse),
+        (status = 400, description = "No tags or an invalid tag", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Photo not found in the album", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn remove_photo_tags(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<PhotoTagsRequest>,
+) -> Result<Json<PhotoTagsResponse>, ApiError> {
+    let tags = normalize_tags(request.tags)?;
+
+    match database::remove_photo_tags(&state.db, &slug, &request.img_url, &tags).await {
+        Ok(Some(tags)) => Ok(Json(PhotoTagsResponse {
+            slug,
+            img_url: request.img_url,
+            tags,
+        })),
+        Ok(None) => Err(photo_not_found(&slug, &request.img_url)),
+        Err(e) => {
+            error!("Failed to remove photo tags: {}", e);
+            Err(ApiError::internal("Failed to remove photo tags"))
+        }
+    }
+}
+
+/// Get photos by tag
+///
+/// Photos of every album carrying `tag`, most recently added first. Their `slug` is the
+/// album they belong to.
+#[utoipa::path(
+    get,
+    path = "/photos/by-tag/{tag}",
+    responses(
+        (status = 200, description = "Photos with the tag", content(
+            ("application/json" = Vec<Album_Content>),
+            ("application/vnd.portfolio.v2+json" = PhotoList)
+        )),
+        (status = 400, description = "Invalid tag", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("tag" = String, Path, description = "Tag, case-insensitive"),
+        EnvelopeQuery
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn get_photos_by_tag(
+    State(state): State<AppState>,
+    format: ListFormat,
+    Path(tag): Path<String>,
+) -> Result<Response, ApiError> {
+    let tag = normalize_tag(&tag)?;
+
+    match database::get_photos_by_tag(&state.db, &tag).await {
+        Ok(mut photos) => {
+            add_thumbnail_variants(photos.iter_mut(), &state.config.thumbnail_sizes);
+            Ok(format.respond(photos))
+        }
+        Err(e) => {
+            error!("Failed to fetch photos by tag: {}", e);
+            Err(ApiError::internal("Failed to fetch photos by tag"))
+        }
+    }
+}
+
+/// Set the cover photo of an album
+///
+/// Use one of the album's photos as its `preview_img_one_url`.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    put,
+    path = "/albums/{slug}/cover",
+    request_body = SetCoverRequest,
+    responses(
+        (status = 200, description = "Cover updated successfully", body = AlbumOperationResponse),
+        (status = 400, description = "The photo is not part of the album", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Album not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Album slug identifier")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Photo Albums"
+)]
+pub async fn set_album_cover(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<SetCoverRequest>,
+) -> Result<Json<AlbumOperationResponse>, ApiError> {
+    // Check if album exists
+    if !database::album_exists(&state.db, &slug).await.map_err(|e| {
+        error!("Failed to check album existence: {}", e);
+        ApiError::internal("Failed to check album existence")
+    })? {
+        return Err(album_not_found(&slug));
+    }
+
+    match database::set_album_cover(&state.db, &slug, &request.img_url).await {
         Ok(true) => {
-            info!("Removed photo: {} from album {}", request.img_url, slug);
+            info!("Set cover of album {} to {}", slug, request.img_url);
             Ok(Json(AlbumOperationResponse {
-                message: "Photo removed successfully".to_string(),
+                message: "Cover updated successfully".to_string(),
                 slug,
             }))
         }
-        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Ok(false) => Err(ApiError::bad_request(
+            "PHOTO_NOT_IN_ALBUM",
+            format!("Photo '{}' is not part of album '{}'", request.img_url, slug),
+        )),
+        Err(e) => {
+            error!("Failed to set album cover: {}", e);
+            Err(ApiError::internal("Failed to set album cover"))
+        }
+    }
+}
+
+/// Longest tag accepted, in characters
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Lowercase a tag and drop a leading `#`, rejecting empty tags, tags longer than
+/// [`MAX_TAG_LENGTH`] and characters other than letters, digits, `-` and `_`
+fn normalize_tag(tag: &str) -> Result<String, ApiError> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+
+    let valid = !tag.is_empty()
+        && tag.chars().count() <= MAX_TAG_LENGTH
+        && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(ApiError::bad_request(
+            "INVALID_TAG",
+            format!(
+                "Invalid tag '{}': use 1 to {} letters, digits, '-' or '_'",
+                tag, MAX_TAG_LENGTH
+            ),
+        ));
+    }
+
+    Ok(tag)
+}
+
+fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, ApiError> {
+    if tags.is_empty() {
+        return Err(ApiError::bad_request("NO_TAGS", "tags must not be empty"));
+    }
+
+    let mut tags = tags.iter().map(|tag| normalize_tag(tag)).collect::<Result<Vec<_>, _>>()?;
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+fn invalid_photo_order(slug: &str) -> ApiError {
+    ApiError::bad_request(
+        "INVALID_PHOTO_ORDER",
+        format!("img_urls must list every photo of album '{}' exactly once", slug),
+    )
+}
+
+fn photo_not_found(slug: &str, img_url: &str) -> ApiError {
+    ApiError::not_found("PHOTO_NOT_FOUND", format!("Photo '{}' not found in album '{}'", img_url, slug))
+}
+
+fn album_changed(slug: &str) -> ApiError {
+    ApiError::precondition_failed(format!(
+        "Album '{}' was changed since the version in If-Match, fetch it again before updating",
+        slug
+    ))
+}
+
+fn album_not_found(slug: &str) -> ApiError {
+    ApiError::not_found("ALBUM_NOT_FOUND", format!("Album '{}' not found", slug))
+}
+
+async fn create_album_error(state: &AppState, slug: &str, e: sqlx::Error) -> ApiError {
+    if database::is_unique_violation(&e) {
+        return album_conflict(state, slug).await;
+    }
+
+    error!("Failed to create album: {}", e);
+    ApiError::internal("Failed to create album")
+}
+
+/// Slug of a new album: `requested` when given, which must be valid and free, otherwise one
+/// derived from `title`, with `-2`, `-3`, ... appended until it is free
+async fn album_slug(state: &AppState, requested: Option<&str>, title: &str) -> Result<String, ApiError> {
+    if let Some(slug) = requested {
+        validate_slug(slug)?;
+        if album_slug_taken(state, slug).await? {
+            return Err(album_conflict(state, slug).await);
+        }
+        return Ok(slug.to_string());
+    }
+
+    let base = slugify(title);
+    if base.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_SLUG",
+            format!("Cannot derive a slug from title '{}', provide one explicitly", title),
+        ));
+    }
+
+    let mut slug = base.clone();
+    let mut suffix = 2;
+    while album_slug_taken(state, &slug).await? {
+        slug = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    Ok(slug)
+}
+
+/// Whether an album, deleted or not, uses `slug`
+async fn album_slug_taken(state: &AppState, slug: &str) -> Result<bool, ApiError> {
+    database::album_slug_in_use(&state.db, slug).await.map_err(|e| {
+        error!("Failed to check existing album: {}", e);
+        ApiError::internal("Failed to check existing album")
+    })
+}
+
+/// Fill in the URLs of the configured thumbnail variants, which are not stored in the database
+fn add_thumbnail_variants<'a>(content: impl Iterator<Item = &'a mut Album_Content>, sizes: &[u32]) {
+    for photo in content {
+        photo.thumbnails = thumbnail_variants(&photo.img_url, sizes);
+    }
+}
+
+fn album_already_exists(slug: &str) -> ApiError {
+    ApiError::conflict("ALBUM_ALREADY_EXISTS", format!("Album '{}' already exists", slug))
+}
+
+/// [`album_already_exists`] with the metadata of the album using `slug`, unless it is in the trash
+async fn album_conflict(state: &AppState, slug: &str) -> ApiError {
+    let conflict = album_already_exists(slug);
+    match database::get_album_metadata(&state.db, slug).await {
+        Ok(Some(existing)) => conflict.with_existing(&existing),
+        Ok(None) => conflict,
+        Err(e) => {
+            warn!("Failed to fetch existing album {}: {}", slug, e);
+            conflict
+        }
+    }
+}
+
+/// Read and parse the `captions` multipart field
+async fn read_captions_field(field: Field<'_>) -> Result<PhotoCaptions, ApiError> {
+    let data = field.text().await.map_err(|e| {
+        error!("Failed to read captions data: {}", e);
+        ApiError::bad_request("INVALID_MULTIPART", "Failed to read captions field")
+    })?;
+
+    serde_json::from_str(&data).map_err(|e| {
+        error!("Failed to parse captions: {}", e);
+        ApiError::bad_request(
+            "INVALID_CAPTIONS",
+            "captions must be a JSON array of strings or an object mapping filenames to captions",
+        )
+    })
+}
+
+/// Match the `captions` field to the uploaded files, in upload order
+///
+/// Files without a caption get `None`, so the caller can apply its default.
+fn resolve_captions(captions: PhotoCaptions, files: &[StagedFile]) -> Result<Vec<Option<String>>, ApiError> {
+    match captions {
+        PhotoCaptions::List(captions) => {
+            if captions.len() != files.len() {
+                return Err(ApiError::bad_request(
+                    "INVALID_CAPTIONS",
+                    format!("Got {} captions for {} files", captions.len(), files.len()),
+                ));
+            }
+            Ok(captions.into_iter().map(Some).collect())
+        }
+        PhotoCaptions::ByFilename(mut captions) => {
+            let resolved = files.iter().map(|f| captions.remove(&f.filename)).collect();
+
+            if let Some(unknown) = captions.keys().next() {
+                return Err(ApiError::bad_request(
+                    "INVALID_CAPTIONS",
+                    format!("Caption given for '{}', which is not one of the uploaded files", unknown),
+                ));
+            }
+            Ok(resolved)
+        }
+    }
+}
+
+/// Move staged files into a new album's directory and insert their content rows in `tx`
+///
+/// Returns the creation response: the added photos, the URLs of the photos that uploaded
+/// duplicates were skipped for, the files that could not be stored and the files stored
+/// recompressed. The first file named `cover_file` becomes
+/// the album cover, or leaves the album without one if it could not be stored. Paths of the
+/// files written are pushed to `written_files` so the caller can remove them if anything fails.
+async fn store_album_files(
+    state: &AppState,
+    tx: &mut Transaction<'_, Postgres>,
+    slug: &str,
+    staged_files: Vec<StagedFile>,
+    file_captions: Vec<Option<String>>,
+    mut cover_file: Option<&str>,
+    written_files: &mut Vec<PathBuf>,
+) -> Result<AddPhotosResponse, ApiError> {
+    let mut added_photos = Vec::new();
+    let mut skipped_duplicates = Vec::new();
+    let mut failed = Vec::new();
+    let mut recompressed = Vec::new();
+
+    let mut duplicates = Vec::with_capacity(staged_files.len());
+    for staged in &staged_files {
+        duplicates.push(duplicate_photo(state, &mut **tx, slug, staged).await?);
+    }
+    let filenames: Vec<String> = staged_files.iter().map(|staged| staged.filename.clone()).collect();
+
+    let uploads = process_uploads(state, slug, &state.config.upload_dir.join(slug), staged_files, duplicates).await;
+    written_files.extend(uploads.iter().filter_map(|upload| match upload {
+        ProcessedUpload::Stored(upload) if !upload.stored.reused => Some(upload.stored.path.clone()),
+        _ => None,
+    }));
+
+    for ((upload, caption), filename) in uploads.into_iter().zip(file_captions).zip(filenames) {
+        let is_cover = cover_file == Some(filename.as_str());
+        if is_cover {
+            cover_file = None;
+        }
+
+        let upload = match upload {
+            ProcessedUpload::Stored(upload) => upload,
+            ProcessedUpload::Duplicate(existing) => {
+                if is_cover {
+                    set_cover_in(tx, slug, &existing).await?;
+                }
+                skipped_duplicates.push(existing);
+                continue;
+            }
+            ProcessedUpload::Failed(failure) => {
+                if is_cover {
+                    set_cover_in(tx, slug, "").await?;
+                }
+                failed.push(failure);
+                continue;
+            }
+        };
+
+        let caption = caption.unwrap_or_else(|| format!("Photo from {}", upload.stored.filename));
+        recompressed.extend(upload.recompressed());
+        let mut content = upload.into_content(state, slug, caption);
+
+        content.created_at = database::add_album_content(&mut **tx, &content).await.map_err(|e| {
+            error!("Failed to add album content: {}", e);
+            ApiError::internal("Failed to add album content")
+        })?;
+        if is_cover {
+            set_cover_in(tx, slug, &content.img_url).await?;
+        }
+
+        info!("Added photo: {} to album {}", content.img_url, slug);
+        added_photos.push(content);
+    }
+
+    Ok(AddPhotosResponse {
+        message: "Album created with files successfully".to_string(),
+        album_slug: slug.to_string(),
+        added_photos,
+        skipped_duplicates,
+        failed,
+        recompressed,
+    })
+}
+
+/// What became of one uploaded file once the files of a request are stored
+enum ProcessedUpload {
+    /// Moved to the album directory, with its thumbnails and WebP copy
+    Stored(StoredUpload),
+    /// Same content as the photo with this URL, already in the album or earlier in the request
+    Duplicate(String),
+    /// Could not be moved to the album directory
+    Failed(FailedUpload),
+}
+
+struct StoredUpload {
+    stored: StoredFile,
+    img_url: String,
+    dimensions: Option<(u32, u32)>,
+    webp_url: Option<String>,
+}
+
+impl StoredUpload {
+    /// The sizes of the file when it was stored recompressed
+    fn recompressed(&self) -> Option<RecompressedUpload> {
+        (!self.stored.reused && self.stored.size < self.stored.original_size).then(|| RecompressedUpload {
+            img_url: self.img_url.clone(),
+            original_size: self.stored.original_size,
+            stored_size: self.stored.size,
+        })
+    }
+
+    /// The content row of the stored file, ready to be inserted
+    fn into_content(self, state: &AppState, slug: &str, caption: String) -> Album_Content {
+        Album_Content {
+            slug: slug.to_string(),
+            thumb_url: thumbnail_url(&self.img_url),
+            thumbnails: thumbnail_variants(&self.img_url, &state.config.thumbnail_sizes),
+            webp_url: self.webp_url,
+            width: self.dimensions.map(|(width, _)| width),
+            height: self.dimensions.map(|(_, height)| height),
+            img_url: self.img_url,
+            caption,
+            tags: Vec::new(),
+            created_at: None,
+        }
+    }
+}
+
+/// Store the staged files of a request in the album directory and generate their thumbnails
+///
+/// Up to `UPLOAD_CONCURRENCY` files are processed at the same time. Files with a
+/// `duplicates` entry are not stored, and with `DEDUPE_UPLOADS` on neither are files with
+/// the same content as an earlier one. The outcomes are returned in upload order.
+async fn process_uploads(
+    state: &AppState,
+    slug: &str,
+    dir: &std::path::Path,
+    staged_files: Vec<StagedFile>,
+    duplicates: Vec<Option<String>>,
+) -> Vec<ProcessedUpload> {
+    let album_dir = state.config.upload_dir.join(slug);
+    let mut outcomes: Vec<Option<ProcessedUpload>> = Vec::with_capacity(staged_files.len());
+    let mut same_as = Vec::new();
+    let mut first_with_hash = HashMap::new();
+    let mut to_store = Vec::new();
+
+    for (index, (staged, duplicate)) in staged_files.into_iter().zip(duplicates).enumerate() {
+        outcomes.push(duplicate.map(ProcessedUpload::Duplicate));
+        if outcomes[index].is_some() {
+            continue;
+        }
+
+        if state.config.dedupe_uploads {
+            if let Some(&first) = first_with_hash.get(&staged.content_hash) {
+                same_as.push((index, first, staged.filename));
+                continue;
+            }
+            first_with_hash.insert(staged.content_hash.clone(), index);
+        }
+        to_store.push((index, staged));
+    }
+
+    let stored: Vec<(usize, ProcessedUpload)> = stream::iter(to_store)
+        .map(|(index, staged)| {
+            let album_dir = &album_dir;
+            async move { (index, process_upload(state, slug, dir, album_dir, staged).await) }
+        })
+        .buffer_unordered(state.config.upload_concurrency)
+        .collect()
+        .await;
+    for (index, outcome) in stored {
+        outcomes[index] = Some(outcome);
+    }
+
+    for (index, first, filename) in same_as {
+        outcomes[index] = match &outcomes[first] {
+            Some(ProcessedUpload::Stored(upload)) => {
+                info!("Skipped {}: same content as {} in the same upload", filename, upload.img_url);
+                Some(ProcessedUpload::Duplicate(upload.img_url.clone()))
+            }
+            Some(ProcessedUpload::Failed(failure)) => Some(ProcessedUpload::Failed(FailedUpload {
+                filename,
+                code: failure.code.clone(),
+                error: failure.error.clone(),
+            })),
+            _ => None,
+        };
+    }
+
+    outcomes.into_iter().flatten().collect()
+}
+
+/// Move one staged file into `dir`, then write its thumbnails, WebP and precompressed copies
+///
+/// `dir` is the album directory, or a directory the files wait in until they are moved to
+/// it with [`publish_upload`]. Either way a file already in `album_dir` is reused with
+/// `DEDUPE_UPLOADS`.
+async fn process_upload(
+    state: &AppState,
+    slug: &str,
+    dir: &std::path::Path,
+    album_dir: &std::path::Path,
+    mut staged: StagedFile,
+) -> ProcessedUpload {
+    let filename = staged.filename.clone();
+    if let Some(quality) = state.config.recompress_quality {
+        staged.recompress(quality).await;
+    }
+    let stored = match staged.store_for(dir, album_dir, state.config.dedupe_uploads).await {
+        Ok(stored) => stored,
         Err(e) => {
-            error!("Failed to remove photo from album: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
-}
-
-/// Check if a file is an image based on its extension
-fn is_image(filename: &str) -> bool {
-    let ext = std::path::Path::new(filename)
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-    
-    matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp")
-}
-
-/// Generate a thumbnail for an image file
-async fn generate_thumbnail(file_path: &std::path::Path, data: &[u8]) {
-    if let Ok(img) = image::load_from_memory(data) {
-        let thumbnail = img.thumbnail(300, 300);
-        
-        let thumb_path = file_path.with_extension(
-            format!("thumb.{}", 
-                file_path.extension().unwrap_or_default().to_str().unwrap_or("jpg")
-            )
-        );
-        
-        if let Err(e) = thumbnail.save(&thumb_path) {
-            error!("Failed to save thumbnail: {}", e);
-        } else {
-            info!("Generated thumbnail: {}", thumb_path.display());
+            warn!("Failed to store {} in album {}: {}", filename, slug, e.message);
+            return ProcessedUpload::Failed(FailedUpload {
+                filename,
+                code: e.code.to_string(),
+                error: e.message,
+            });
+        }
+    };
+
+    // Generate thumbnails and read the size if it's an image
+    let dimensions =
+        process_image(&stored.path, stored.reused, state.config.thumbnail_max_size, &state.config.thumbnail_sizes).await;
+
+    let img_url = format!("/files/{}/{}", slug, stored.stored_name);
+    let webp_url = if state.config.convert_to_webp {
+        write_webp_copy(&stored.path, &img_url, state.config.webp_quality).await
+    } else {
+        None
+    };
+    if state.config.precompress_uploads {
+        write_precompressed(&stored.path).await;
+    }
+
+    ProcessedUpload::Stored(StoredUpload {
+        stored,
+        img_url,
+        dimensions,
+        webp_url,
+    })
+}
+
+/// Point the cover of the album being created at one of its photos
+async fn set_cover_in(tx: &mut Transaction<'_, Postgres>, slug: &str, img_url: &str) -> Result<(), ApiError> {
+    database::set_album_cover(&mut **tx, slug, img_url).await.map(|_| ()).map_err(|e| {
+        error!("Failed to set album cover: {}", e);
+        ApiError::internal("Failed to set album cover")
+    })
+}
+
+/// Whether a cover URL points at something to display: any absolute http(s) URL, or a
+/// `/files/` URL of a file that exists
+async fn cover_exists(state: &AppState, url: &str) -> bool {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        return true;
+    }
+
+    match file_path(&state.config.upload_dir, url) {
+        Some(path) => fs::metadata(&path).await.is_ok_and(|metadata| metadata.is_file()),
+        None => false,
+    }
+}
+
+/// Replace a `missing` (or unset) cover so the album is never shown with a broken one
+///
+/// The stand-in is the thumbnail (or the file) of the first of `candidates`, the album's
+/// first photos in display order, that exists on disk, then `DEFAULT_COVER_URL`. The stored
+/// value is kept in `raw_preview_url` for editing.
+async fn resolve_cover(state: &AppState, metadata: &mut Album_Metadata, missing: bool, candidates: &[Album_Content]) {
+    metadata.raw_preview_url = Some(metadata.preview_img_one_url.clone());
+    if !missing {
+        return;
+    }
+
+    for photo in candidates {
+        for url in photo.thumb_url.iter().chain([&photo.img_url]) {
+            if cover_exists(state, url).await {
+                metadata.preview_img_one_url = url.clone();
+                return;
+            }
+        }
+    }
+    if let Some(default_cover_url) = &state.config.default_cover_url {
+        metadata.preview_img_one_url = default_cover_url.clone();
+    }
+}
+
+/// Add a photo row in its own savepoint, so a failed insert does not undo the rows added before
+async fn add_content_in(tx: &mut Transaction<'_, Postgres>, content: &Album_Content) -> Result<Option<String>, sqlx::Error> {
+    let mut savepoint = tx.begin().await?;
+    let created_at = database::add_album_content(&mut *savepoint, content).await?;
+    savepoint.commit().await?;
+    Ok(created_at)
+}
+
+/// `409 Conflict` for a batch that would take the album past `MAX_PHOTOS_PER_ALBUM` photos
+fn album_full(slug: &str, photo_count: i64, incoming: usize, max_photos: usize) -> ApiError {
+    ApiError::conflict(
+        "ALBUM_FULL",
+        format!(
+            "Album '{}' has {} photos, adding {} would exceed the limit of {}",
+            slug, photo_count, incoming, max_photos
+        ),
+    )
+}
+
+/// Check that a `preview_img_one_url` can be displayed
+///
+/// Empty (no cover) and absolute `http(s)://` URLs are accepted as is, `/files/` URLs must
+/// point at an existing file.
+async fn validate_cover_url(state: &AppState, url: &str) -> Result<(), ApiError> {
+    if url.is_empty() || url.starts_with("https://") || url.starts_with("http://") {
+        return Ok(());
+    }
+
+    if !url.starts_with("/files/") {
+        return Err(ApiError::bad_request(
+            "INVALID_COVER_URL",
+            format!(
+                "preview_img_one_url '{}' must be an absolute http(s) URL or a /files/ URL",
+                url
+            ),
+        ));
+    }
+
+    if !cover_exists(state, url).await {
+        return Err(ApiError::bad_request(
+            "COVER_NOT_FOUND",
+            format!("preview_img_one_url '{}' does not match an uploaded file", url),
+        ));
+    }
+
+    Ok(())
+}
+
+/// With `DEDUPE_UPLOADS` on, the URL of the album photo that has the same content as `staged`
+async fn duplicate_photo(
+    state: &AppState,
+    executor: impl sqlx::PgExecutor<'_>,
+    slug: &str,
+    staged: &StagedFile,
+) -> Result<Option<String>, ApiError> {
+    if !state.config.dedupe_uploads {
+        return Ok(None);
+    }
+
+    let existing = database::find_album_content_by_hash(executor, slug, &staged.content_hash)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up duplicate photos: {}", e);
+            ApiError::internal("Failed to add album content")
+        })?;
+    if let Some(img_url) = &existing {
+        info!("Skipped {}: same content as {} in album {}", staged.filename, img_url, slug);
+    }
+    Ok(existing)
+}
+
+/// Undo the file side of a failed album creation
+///
+/// Removes the whole album directory when this request created it, otherwise only
+/// the files (and the thumbnails, WebP and precompressed copies) it wrote.
+async fn remove_written_files(
+    album_dir: &std::path::Path,
+    created_dir: bool,
+    written_files: &[PathBuf],
+    thumbnail_sizes: &[u32],
+) {
+    if created_dir {
+        if let Err(e) = fs::remove_dir_all(album_dir).await {
+            error!("Failed to remove album directory {}: {}", album_dir.display(), e);
+        }
+        return;
+    }
+
+    for path in written_files {
+        for path in std::iter::once(path.clone()).chain(generated_files(path, thumbnail_sizes)) {
+            if let Err(e) = fs::remove_file(&path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    error!("Failed to remove file {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Files written next to an upload: its thumbnails, WebP and precompressed copies
+fn generated_files(path: &std::path::Path, thumbnail_sizes: &[u32]) -> Vec<PathBuf> {
+    let thumbnails = thumbnail_sizes.iter().map(|&size| thumbnail_variant_path(path, size));
+    [thumbnail_path(path), webp_path(path)]
+        .into_iter()
+        .chain(precompressed_paths(path))
+        .chain(thumbnails)
+        .collect()
+}
+
+/// Move an upload processed outside the album, and the files generated for it, into `album_dir`
+///
+/// With `DEDUPE_UPLOADS`, a file stored meanwhile under the same name by another upload is
+/// reused instead, and the copies processed here are left behind.
+async fn publish_upload(upload: &mut StoredUpload, album_dir: &std::path::Path, state: &AppState) -> std::io::Result<()> {
+    if upload.stored.reused {
+        return Ok(());
+    }
+
+    let target = album_dir.join(&upload.stored.stored_name);
+    if state.config.dedupe_uploads && fs::try_exists(&target).await.unwrap_or(false) {
+        upload.stored.path = target;
+        upload.stored.reused = true;
+        return Ok(());
+    }
+
+    fs::rename(&upload.stored.path, &target).await?;
+    let moved = generated_files(&target, &state.config.thumbnail_sizes);
+    for (from, to) in generated_files(&upload.stored.path, &state.config.thumbnail_sizes).into_iter().zip(moved) {
+        match fs::rename(&from, &to).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                // The file itself is in, the copy is left out rather than failing the upload
+                error!("Failed to move {} to {}: {}", from.display(), to.display(), e);
+            }
+        }
+    }
+    upload.stored.path = target;
+    Ok(())
+}
+
+/// A name for `file_name` in `dir` that neither it nor its generated files use yet
+///
+/// Taken names get `-2`, `-3`, ... before their content hash (`photo_a1b2c3d4.jpg` ->
+/// `photo-2_a1b2c3d4.jpg`), so duplicate detection still recognizes them.
+fn free_file_name(dir: &std::path::Path, file_name: &str, thumbnail_sizes: &[u32]) -> String {
+    let taken = |name: &str| {
+        let path = dir.join(name);
+        path.exists()
+            || thumbnail_path(&path).exists()
+            || webp_path(&path).exists()
+            || precompressed_paths(&path).iter().any(|copy| copy.exists())
+            || thumbnail_sizes.iter().any(|&size| thumbnail_variant_path(&path, size).exists())
+    };
+    if !taken(file_name) {
+        return file_name.to_string();
+    }
+
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, format!(".{}", extension)),
+        None => (file_name, String::new()),
+    };
+    let (name, hash) = match stem.rsplit_once('_') {
+        Some((name, hash)) => (name, format!("_{}", hash)),
+        None => (stem, String::new()),
+    };
+    (2..)
+        .map(|suffix| format!("{}-{}{}{}", name, suffix, hash, extension))
+        .find(|candidate| !taken(candidate))
+        .expect("an unused file name")
+}
+
+/// Move a photo file and the thumbnails and copies generated from it next to `target`,
+/// returning the `(from, to)` pairs that were moved
+///
+/// Already moved files are put back if one of the moves fails.
+async fn move_photo_files(
+    source: &std::path::Path,
+    target: &std::path::Path,
+    thumbnail_sizes: &[u32],
+) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut pairs = vec![
+        (source.to_path_buf(), target.to_path_buf()),
+        (thumbnail_path(source), thumbnail_path(target)),
+        (webp_path(source), webp_path(target)),
+    ];
+    pairs.extend(precompressed_paths(source).into_iter().zip(precompressed_paths(target)));
+    pairs.extend(
+        thumbnail_sizes
+            .iter()
+            .map(|&size| (thumbnail_variant_path(source, size), thumbnail_variant_path(target, size))),
+    );
+
+    let mut moved = Vec::new();
+    for (from, to) in pairs {
+        if !from.exists() {
+            continue;
+        }
+        if let Err(e) = fs::rename(&from, &to).await {
+            restore_files(&moved).await;
+            return Err(e);
+        }
+        moved.push((from, to));
+    }
+    Ok(moved)
+}
+
+/// Undo `move_photo_files`
+async fn restore_files(moved: &[(PathBuf, PathBuf)]) {
+    for (from, to) in moved.iter().rev() {
+        if let Err(e) = fs::rename(to, from).await {
+            error!("Failed to move {} back to {}: {}", to.display(), from.display(), e);
         }
     }
 }
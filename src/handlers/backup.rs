@@ -0,0 +1,293 @@
+//! Backup Handlers
+//!
+//! This module contains the export endpoint, which dumps all portfolio metadata (projects,
+//! albums and album content) as a single JSON document, and the import endpoint restoring
+//! such a document.
+
+use axum::{
+    body::Body,
+    extract::{rejection::QueryRejection, Query, State},
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{SecondsFormat, Utc};
+use futures_util::{Stream, TryStreamExt};
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use std::{collections::HashMap, pin::pin};
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio_util::io::ReaderStream;
+use tracing::{error, info};
+use utoipa;
+
+use crate::{
+    database::{self, ImportOutcome},
+    error::ApiError,
+    handlers::{parse_date, validate_lang},
+    models::*,
+    AppState,
+};
+
+/// Format version written in exported documents
+///
+/// Version 2 moved project texts to `translations`; version 1 documents, with the `en_*`/`fr_*`
+/// fields, are still imported.
+pub const EXPORT_VERSION: u32 = 2;
+
+/// Size of the buffer between the export writer and the response body
+const EXPORT_BUFFER_BYTES: usize = 64 * 1024;
+
+type ExportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Export all content
+///
+/// Returns every development project, album and album photo as one JSON document, to be
+/// kept as a backup. Uploaded files are not included. The document is streamed while it is
+/// read from a consistent database snapshot, so memory use does not grow with the dataset.
+///
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    get,
+    path = "/export",
+    responses(
+        (status = 200, description = "Export document", body = ExportDocument, content_type = "application/json"),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Backup"
+)]
+pub async fn export_content(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let tx = begin_snapshot(&state).await.map_err(|e| {
+        error!("Failed to start export: {}", e);
+        ApiError::internal("Failed to start export")
+    })?;
+
+    let exported_at = Utc::now();
+    let (writer, reader) = tokio::io::duplex(EXPORT_BUFFER_BYTES);
+    tokio::spawn(async move {
+        match write_export(tx, exported_at.to_rfc3339_opts(SecondsFormat::Secs, true), writer).await {
+            Ok(()) => info!("Export completed"),
+            // The client receives a truncated, invalid JSON document
+            Err(e) => error!("Failed to write export: {}", e),
+        }
+    });
+
+    let filename = format!("portfolio-export-{}.json", exported_at.format("%Y-%m-%d"));
+    Ok((
+        [
+            (CONTENT_TYPE, "application/json".to_string()),
+            (CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(ReaderStream::new(reader)),
+    )
+        .into_response())
+}
+
+/// Open a read-only transaction that sees one snapshot for all the export queries
+async fn begin_snapshot(state: &AppState) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = state.db.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    Ok(tx)
+}
+
+/// Write the export document, laid out like [`ExportDocument`]
+async fn write_export(
+    mut tx: Transaction<'static, Postgres>,
+    exported_at: String,
+    writer: impl AsyncWrite + Unpin,
+) -> Result<(), ExportError> {
+    let mut out = BufWriter::new(writer);
+
+    out.write_all(b"{\"version\":").await?;
+    out.write_all(EXPORT_VERSION.to_string().as_bytes()).await?;
+    out.write_all(b",\"exported_at\":").await?;
+    out.write_all(&serde_json::to_vec(&exported_at)?).await?;
+
+    write_array(&mut out, "dev_projects", database::stream_dev_projects(&mut *tx)).await?;
+    write_array(&mut out, "albums", database::stream_albums(&mut *tx)).await?;
+    write_array(&mut out, "album_content", database::stream_album_content(&mut *tx)).await?;
+
+    out.write_all(b"}").await?;
+    out.shutdown().await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Write `,"name":[...]` with one element per streamed item
+async fn write_array<T: Serialize>(
+    out: &mut (impl AsyncWrite + Unpin),
+    name: &str,
+    items: impl Stream<Item = Result<T, sqlx::Error>>,
+) -> Result<(), ExportError> {
+    out.write_all(format!(",\"{}\":[", name).as_bytes()).await?;
+
+    let mut items = pin!(items);
+    let mut first = true;
+    while let Some(item) = items.try_next().await? {
+        if !first {
+            out.write_all(b",").await?;
+        }
+        first = false;
+        out.write_all(&serde_json::to_vec(&item)?).await?;
+    }
+
+    out.write_all(b"]").await?;
+    Ok(())
+}
+
+/// Import content
+///
+/// Restores projects, albums and album content from a document produced by `GET /export`,
+/// in a single transaction. Only metadata is restored: the files must already be in the
+/// upload directory. Photos are imported in the order they are listed for their album.
+///
+/// `mode` decides what happens to projects and albums whose slug already exists: `skip`
+/// keeps the existing one (photos of a skipped album are skipped too), `overwrite` replaces
+/// it, and `fail` (the default) aborts the import with a 409 and leaves the database untouched.
+///
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/import",
+    params(ImportQuery),
+    request_body = ExportDocument,
+    responses(
+        (status = 200, description = "Content imported", body = ImportResponse),
+        (status = 400, description = "Invalid import document or mode", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 409, description = "A slug already exists and mode is `fail`", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Backup"
+)]
+pub async fn import_content(
+    State(state): State<AppState>,
+    query: Result<Query<ImportQuery>, QueryRejection>,
+    Json(mut document): Json<ExportDocument>,
+) -> Result<Json<ImportResponse>, ApiError> {
+    let Query(ImportQuery { mode }) = query.map_err(|_| {
+        ApiError::bad_request("INVALID_IMPORT_MODE", "mode must be one of skip, overwrite or fail")
+    })?;
+    validate_import(&mut document)?;
+
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!("Failed to start import: {}", e);
+        ApiError::internal("Failed to start import")
+    })?;
+
+    let mut report = ImportResponse {
+        dev_projects: ImportCounts::default(),
+        albums: ImportCounts::default(),
+        album_content: ImportCounts::default(),
+    };
+
+    for project in &document.dev_projects {
+        let outcome = database::import_dev_project(&mut tx, project, mode)
+            .await
+            .map_err(|e| import_error("Project", &project.slug, e))?;
+        record(&mut report.dev_projects, outcome);
+    }
+
+    let mut album_outcomes = HashMap::new();
+    for album in &document.albums {
+        let outcome = database::import_album(&mut *tx, album, mode)
+            .await
+            .map_err(|e| import_error("Album", &album.slug, e))?;
+        record(&mut report.albums, outcome);
+        album_outcomes.insert(album.slug.as_str(), outcome);
+    }
+
+    let mut positions: HashMap<&str, i32> = HashMap::new();
+    for content in &document.album_content {
+        if album_outcomes.get(content.slug.as_str()) == Some(&ImportOutcome::Skipped) {
+            record(&mut report.album_content, ImportOutcome::Skipped);
+            continue;
+        }
+
+        let position = positions.entry(content.slug.as_str()).or_insert(0);
+        let outcome = database::import_album_content(&mut tx, content, *position)
+            .await
+            .map_err(|e| import_error("Photo", &content.img_url, e))?;
+        *position += 1;
+        record(&mut report.album_content, outcome);
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!("Failed to commit import: {}", e);
+        ApiError::internal("Failed to commit import")
+    })?;
+
+    info!(
+        "Imported {} projects, {} albums and {} photos ({:?} mode)",
+        document.dev_projects.len(),
+        document.albums.len(),
+        document.album_content.len(),
+        mode
+    );
+    Ok(Json(report))
+}
+
+/// Check an import document before touching the database, normalizing its dates
+fn validate_import(document: &mut ExportDocument) -> Result<(), ApiError> {
+    if !(1..=EXPORT_VERSION).contains(&document.version) {
+        return Err(ApiError::bad_request(
+            "UNSUPPORTED_EXPORT_VERSION",
+            format!("Unsupported export version {}, expected 1 to {}", document.version, EXPORT_VERSION),
+        ));
+    }
+
+    for project in &mut document.dev_projects {
+        let translations = std::mem::take(&mut project.translations);
+        project.translations = translations
+            .into_iter()
+            .map(|(lang, translation)| Ok((validate_lang(&lang)?, translation)))
+            .collect::<Result<_, ApiError>>()?;
+    }
+
+    for project in &mut document.dev_projects {
+        project.date = parse_date("date", &project.date)?.to_string();
+    }
+    for album in &mut document.albums {
+        album.date = parse_date("date", &album.date)?.to_string();
+    }
+
+    if let Some(content) = document
+        .album_content
+        .iter()
+        .find(|content| !document.albums.iter().any(|album| album.slug == content.slug))
+    {
+        return Err(ApiError::bad_request(
+            "INVALID_IMPORT",
+            format!("Photo '{}' belongs to album '{}', which is not in the document", content.img_url, content.slug),
+        ));
+    }
+
+    Ok(())
+}
+
+fn import_error(kind: &str, key: &str, e: sqlx::Error) -> ApiError {
+    if database::is_unique_violation(&e) {
+        return ApiError::conflict("IMPORT_CONFLICT", format!("{} '{}' already exists", kind, key));
+    }
+
+    error!("Failed to import {} '{}': {}", kind.to_lowercase(), key, e);
+    ApiError::internal("Failed to import content")
+}
+
+fn record(counts: &mut ImportCounts, outcome: ImportOutcome) {
+    match outcome {
+        ImportOutcome::Created => counts.created += 1,
+        ImportOutcome::Updated => counts.updated += 1,
+        ImportOutcome::Skipped => counts.skipped += 1,
+    }
+}
@@ -5,64 +5,197 @@
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    http::{HeaderMap, StatusCode},
+    response::{Json, Response},
 };
-use tracing::error;
+use axum_extra::extract::Query;
+use std::collections::BTreeMap;
+use tracing::{error, info};
 use utoipa;
 
-use crate::{database, models::*, AppState};
+use crate::{
+    database::{self, ProjectCursor, ProjectPage},
+    error::ApiError,
+    handlers::{created_at, date_range, parse_date, validate_lang, validate_slug, ListFormat, ProjectLanguage},
+    middleware::RequireAdmin,
+    models::*,
+    AppState,
+};
 
 /// Get all development projects
 ///
-/// Returns a list of all development projects in the portfolio
+/// Returns a list of all development projects in the portfolio.
+/// Use `?tag=backend` to only list projects with that tag; repeat `tag` to match any of several tags.
+/// Use `?from=2024-01-01&to=2024-12-31` to only list projects dated within that range (inclusive).
+///
+/// With `?limit=20` one page is returned, and the `next_cursor` of the envelope (or the
+/// `X-Next-Cursor` header) is passed as `?cursor=` to get the next one. Unlike `?offset=`,
+/// cursors neither skip nor repeat projects when others are added or removed between pages.
+///
+/// Projects come with the `title` and `short_description` of one language, picked with
+/// `?lang=fr` or the `Accept-Language` header (English by default or when a project lacks
+/// the language); `?lang=all` returns every translation, as `Dev_Project_Metadata`.
 #[utoipa::path(
     get,
     path = "/dev-projects",
+    params(DevProjectFilter, LangQuery, EnvelopeQuery),
     responses(
-        (status = 200, description = "List of development projects", body = [Dev_Project_Metadata]),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "List of development projects", content(
+            ("application/json" = [LocalizedDevProject]),
+            ("application/vnd.portfolio.v2+json" = LocalizedDevProjectList)
+        )),
+        (status = 400, description = "Invalid date filter, pagination or language", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     tag = "Development Projects"
 )]
 pub async fn get_dev_projects(
     State(state): State<AppState>,
-) -> Result<Json<Vec<Dev_Project_Metadata>>, StatusCode> {
-    match database::get_all_dev_projects(&state.db).await {
-        Ok(projects) => Ok(Json(projects)),
+    format: ListFormat,
+    lang: ProjectLanguage,
+    Query(filter): Query<DevProjectFilter>,
+) -> Result<Response, ApiError> {
+    let range = date_range(filter.from.as_deref(), filter.to.as_deref())?;
+    let page = project_page(&filter)?;
+
+    let mut projects = database::get_dev_projects(&state.db, &filter.tag, range, &page).await.map_err(|e| {
+        error!("Failed to fetch dev projects: {}", e);
+        ApiError::internal("Failed to fetch dev projects")
+    })?;
+
+    // One project more than the page size was fetched to know whether there is a next page
+    let next_cursor = match filter.limit {
+        Some(limit) if projects.len() > limit as usize => {
+            projects.truncate(limit as usize);
+            projects.last().and_then(ProjectCursor::after).map(|cursor| cursor.encode())
+        }
+        _ => None,
+    };
+
+    Ok(lang.respond_list(format, projects, next_cursor))
+}
+
+/// The page of projects asked for by `limit` and either `cursor` or `offset`
+fn project_page(filter: &DevProjectFilter) -> Result<ProjectPage, ApiError> {
+    if filter.limit == Some(0) {
+        return Err(ApiError::bad_request("INVALID_PAGINATION", "limit must be at least 1"));
+    }
+    if filter.cursor.is_some() && filter.offset.is_some() {
+        return Err(ApiError::bad_request("INVALID_PAGINATION", "cursor and offset cannot be combined"));
+    }
+
+    let after = match filter.cursor.as_deref() {
+        Some(cursor) => Some(ProjectCursor::decode(cursor).ok_or_else(|| {
+            ApiError::bad_request("INVALID_CURSOR", format!("'{}' is not a cursor returned by this endpoint", cursor))
+        })?),
+        None => None,
+    };
+
+    Ok(ProjectPage {
+        after,
+        offset: i64::from(filter.offset.unwrap_or(0)),
+        limit: filter.limit.map(|limit| i64::from(limit) + 1),
+    })
+}
+
+/// Search development projects
+///
+/// Matches `q` case-insensitively against the English and French titles and descriptions,
+/// techs and tags. Projects matching in a title are listed first. Results are localized like
+/// `GET /dev-projects`.
+#[utoipa::path(
+    get,
+    path = "/dev-projects/search",
+    params(SearchQuery, LangQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "Matching development projects", content(
+            ("application/json" = [LocalizedDevProject]),
+            ("application/vnd.portfolio.v2+json" = LocalizedDevProjectList)
+        )),
+        (status = 400, description = "Missing or empty search query, or invalid language", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Development Projects"
+)]
+pub async fn search_dev_projects(
+    State(state): State<AppState>,
+    format: ListFormat,
+    lang: ProjectLanguage,
+    Query(search): Query<SearchQuery>,
+) -> Result<Response, ApiError> {
+    let query = search.q.as_deref().map(str::trim).unwrap_or_default();
+    if query.is_empty() {
+        return Err(ApiError::bad_request("MISSING_QUERY", "Search query 'q' must not be empty"));
+    }
+
+    match database::search_dev_projects(&state.db, query).await {
+        Ok(projects) => Ok(lang.respond_list(format, projects, None)),
         Err(e) => {
-            error!("Failed to fetch dev projects: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            error!("Failed to search dev projects: {}", e);
+            Err(ApiError::internal("Failed to search dev projects"))
+        }
+    }
+}
+
+/// Get the techs used across projects
+///
+/// Returns the distinct techs of all development projects, sorted alphabetically, each with
+/// the number of projects using it. Techs differing only in case are counted together.
+#[utoipa::path(
+    get,
+    path = "/dev-projects/techs",
+    params(EnvelopeQuery),
+    responses(
+        (status = 200, description = "Distinct techs with their project count", content(
+            ("application/json" = [TechCount]),
+            ("application/vnd.portfolio.v2+json" = TechList)
+        )),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Development Projects"
+)]
+pub async fn get_dev_project_techs(
+    State(state): State<AppState>,
+    format: ListFormat,
+) -> Result<Response, ApiError> {
+    match database::get_all_techs(&state.db).await {
+        Ok(techs) => Ok(format.respond(techs)),
+        Err(e) => {
+            error!("Failed to fetch dev project techs: {}", e);
+            Err(ApiError::internal("Failed to fetch dev project techs"))
         }
     }
 }
 
 /// Get a specific development project by slug
 ///
-/// Returns detailed information about a development project
+/// Returns detailed information about a development project, localized like `GET /dev-projects`
 #[utoipa::path(
     get,
     path = "/dev-projects/{slug}",
     responses(
-        (status = 200, description = "Development project details", body = Dev_Project_Metadata),
-        (status = 404, description = "Project not found"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Development project details (`Dev_Project_Metadata` with `?lang=all`)", body = LocalizedDevProject),
+        (status = 400, description = "Invalid language", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
-        ("slug" = String, Path, description = "Project slug identifier")
+        ("slug" = String, Path, description = "Project slug identifier"),
+        LangQuery
     ),
     tag = "Development Projects"
 )]
 pub async fn get_dev_project(
     State(state): State<AppState>,
+    lang: ProjectLanguage,
     Path(slug): Path<String>,
-) -> Result<Json<Dev_Project_Metadata>, StatusCode> {
+) -> Result<Response, ApiError> {
     match database::get_dev_project_by_slug(&state.db, &slug).await {
-        Ok(Some(project)) => Ok(Json(project)),
-        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Ok(Some(project)) => Ok(lang.respond(project)),
+        Ok(None) => Err(project_not_found(&slug)),
         Err(e) => {
             error!("Failed to fetch dev project: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to fetch dev project"))
         }
     }
 }
@@ -71,58 +204,81 @@ pub async fn get_dev_project(
 ///
 /// Create a new development project in the portfolio
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     post,
     path = "/dev-projects",
     request_body = CreateDevProjectRequest,
     responses(
-        (status = 201, description = "Project created successfully", body = ProjectOperationResponse),
-        (status = 400, description = "Invalid request data"),
-        (status = 409, description = "Project with this slug already exists"),
-        (status = 500, description = "Internal server error")
+        (status = 201, description = "Project created successfully", body = ProjectOperationResponse,
+            headers(("Location" = String, description = "URL of the new project"))),
+        (status = 400, description = "Invalid request data", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 409, description = "Project with this slug already exists, or the Idempotency-Key is used by a running request", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replay the response of an earlier successful request sent with the same key instead of creating again")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Development Projects"
 )]
 pub async fn create_dev_project(
     State(state): State<AppState>,
     Json(request): Json<CreateDevProjectRequest>,
-) -> Result<Json<ProjectOperationResponse>, StatusCode> {
+) -> Result<(StatusCode, HeaderMap, Json<ProjectOperationResponse>), ApiError> {
+    validate_slug(&request.slug)?;
+
     // Check if project with this slug already exists
     match database::get_dev_project_by_slug(&state.db, &request.slug).await {
-        Ok(Some(_)) => {
-            return Err(StatusCode::CONFLICT);
-        }
+        Ok(Some(existing)) => return Err(project_already_exists(&request.slug).with_existing(&existing)),
         Ok(None) => {} // OK, project doesn't exist
         Err(e) => {
             error!("Failed to check existing project: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("Failed to check existing project"));
         }
-    }    // Convert request to Dev_Project_Metadata
+    }
+
+    let mut translations = normalize_translations(request.translations)?;
+    request.legacy.merge_into(&mut translations).map_err(invalid_translations)?;
+    require_default_language(&translations)?;
+
+    // Convert request to Dev_Project_Metadata
     let project = Dev_Project_Metadata {
         slug: request.slug.clone(),
-        en_title: request.en_title,
-        en_short_description: request.en_short_description,
-        fr_title: request.fr_title,
-        fr_short_description: request.fr_short_description,
+        translations,
         techs: request.techs,
         link: request.link,
-        date: request.date,
+        date: parse_date("date", &request.date)?.to_string(),
         tags: request.tags,
         priority: request.priority.unwrap_or(0),
+        created_at: None,
+        updated_at: None,
     };
 
     match database::create_dev_project(&state.db, &project).await {
-        Ok(_) => Ok(Json(ProjectOperationResponse {
-            message: "Project created successfully".to_string(),
-            slug: request.slug,
-        })),
+        Ok(_) => Ok((
+            StatusCode::CREATED,
+            created_at("/dev-projects", &request.slug),
+            Json(ProjectOperationResponse {
+                message: "Project created successfully".to_string(),
+                slug: request.slug,
+            }),
+        )),
+        // Created concurrently, or in the trash (which keeps its slug)
+        Err(e) if database::is_unique_violation(&e) => {
+            let conflict = project_already_exists(&request.slug);
+            match database::get_dev_project_by_slug(&state.db, &request.slug).await {
+                Ok(Some(existing)) => Err(conflict.with_existing(&existing)),
+                _ => Err(conflict),
+            }
+        }
         Err(e) => {
             error!("Failed to create dev project: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to create dev project"))
         }
     }
 }
@@ -131,22 +287,24 @@ pub async fn create_dev_project(
 ///
 /// Update an existing development project. Only provided fields will be updated.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     put,
     path = "/dev-projects/{slug}",
     request_body = UpdateDevProjectRequest,
     responses(
         (status = 200, description = "Project updated successfully", body = ProjectOperationResponse),
-        (status = 400, description = "Invalid request data"),
-        (status = 404, description = "Project not found"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Invalid request data", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
         ("slug" = String, Path, description = "Project slug identifier")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Development Projects"
 )]
@@ -154,30 +312,30 @@ pub async fn update_dev_project(
     State(state): State<AppState>,
     Path(slug): Path<String>,
     Json(request): Json<UpdateDevProjectRequest>,
-) -> Result<Json<ProjectOperationResponse>, StatusCode> {
+) -> Result<Json<ProjectOperationResponse>, ApiError> {
     // Get existing project
     let mut existing_project = match database::get_dev_project_by_slug(&state.db, &slug).await {
         Ok(Some(project)) => project,
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Ok(None) => return Err(project_not_found(&slug)),
         Err(e) => {
             error!("Failed to fetch existing project: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("Failed to fetch existing project"));
         }
     };
 
     // Update only provided fields
-    if let Some(en_title) = request.en_title {
-        existing_project.en_title = en_title;
-    }
-    if let Some(en_short_description) = request.en_short_description {
-        existing_project.en_short_description = en_short_description;
-    }
-    if let Some(fr_title) = request.fr_title {
-        existing_project.fr_title = fr_title;
-    }
-    if let Some(fr_short_description) = request.fr_short_description {
-        existing_project.fr_short_description = fr_short_description;
+    for (lang, translation) in request.translations.unwrap_or_default() {
+        let lang = validate_lang(&lang)?;
+        match translation {
+            Some(translation) => existing_project.translations.insert(lang, translation),
+            None => existing_project.translations.remove(&lang),
+        };
     }
+    request
+        .legacy
+        .merge_into(&mut existing_project.translations)
+        .map_err(invalid_translations)?;
+    require_default_language(&existing_project.translations)?;
     if let Some(techs) = request.techs {
         existing_project.techs = techs;
     }
@@ -185,8 +343,9 @@ pub async fn update_dev_project(
         existing_project.link = link;
     }
     if let Some(date) = request.date {
-        existing_project.date = date;
-    }    if let Some(tags) = request.tags {
+        existing_project.date = parse_date("date", &date)?.to_string();
+    }
+    if let Some(tags) = request.tags {
         existing_project.tags = tags;
     }
     if let Some(priority) = request.priority {
@@ -198,48 +357,261 @@ pub async fn update_dev_project(
             message: "Project updated successfully".to_string(),
             slug,
         })),
-        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Ok(false) => Err(project_not_found(&slug)),
         Err(e) => {
             error!("Failed to update dev project: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to update dev project"))
+        }
+    }
+}
+
+/// Rename a development project
+///
+/// Change the slug of a development project.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/dev-projects/{slug}/rename",
+    request_body = RenameRequest,
+    responses(
+        (status = 200, description = "Project renamed, the response holds the new slug", body = ProjectOperationResponse),
+        (status = 400, description = "Invalid new slug", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 409, description = "A project already uses the new slug", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Current project slug")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Development Projects"
+)]
+pub async fn rename_dev_project(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<RenameRequest>,
+) -> Result<Json<ProjectOperationResponse>, ApiError> {
+    let new_slug = request.new_slug;
+    validate_slug(&new_slug)?;
+    if new_slug == slug {
+        return Err(ApiError::bad_request("SAME_SLUG", format!("Project is already named '{}'", slug)));
+    }
+
+    match database::rename_dev_project(&state.db, &slug, &new_slug).await {
+        Ok(true) => {
+            info!("Renamed dev project {} to {}", slug, new_slug);
+            Ok(Json(ProjectOperationResponse {
+                message: format!("Project renamed from '{}' to '{}'", slug, new_slug),
+                slug: new_slug,
+            }))
+        }
+        Ok(false) => Err(project_not_found(&slug)),
+        Err(e) if database::is_unique_violation(&e) => Err(project_already_exists(&new_slug)),
+        Err(e) => {
+            error!("Failed to rename dev project: {}", e);
+            Err(ApiError::internal("Failed to rename dev project"))
         }
     }
 }
 
+/// Reorder development projects
+///
+/// Rewrite the `priority` of the listed projects to follow their order in `slugs`
+/// (0, 1, 2, ...) in a single transaction. Slugs that match no project are skipped and
+/// reported in `not_found`; with `"strict": true` the request is rejected with a `404`
+/// and nothing changes.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    put,
+    path = "/dev-projects/reorder",
+    request_body = ReorderProjectsRequest,
+    responses(
+        (status = 200, description = "Projects reordered", body = ReorderProjectsResponse),
+        (status = 400, description = "Empty list or duplicate slug", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Strict mode and a slug matches no project", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Development Projects"
+)]
+pub async fn reorder_dev_projects(
+    State(state): State<AppState>,
+    Json(request): Json<ReorderProjectsRequest>,
+) -> Result<Json<ReorderProjectsResponse>, ApiError> {
+    if request.slugs.is_empty() {
+        return Err(ApiError::bad_request("NO_PROJECTS", "slugs must list at least one project"));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = request.slugs.iter().find(|slug| !seen.insert(slug.as_str())) {
+        return Err(ApiError::bad_request(
+            "DUPLICATE_SLUG",
+            format!("Project '{}' is listed more than once", duplicate),
+        ));
+    }
+
+    let not_found = database::reorder_dev_projects(&state.db, &request.slugs, request.strict)
+        .await
+        .map_err(|e| {
+            error!("Failed to reorder dev projects: {}", e);
+            ApiError::internal("Failed to reorder dev projects")
+        })?;
+    if request.strict && !not_found.is_empty() {
+        return Err(ApiError::not_found(
+            "PROJECT_NOT_FOUND",
+            format!("Projects not found: {}", not_found.join(", ")),
+        ));
+    }
+
+    let updated = request.slugs.len() - not_found.len();
+    info!("Reordered {} dev projects", updated);
+    Ok(Json(ReorderProjectsResponse {
+        message: format!("Reordered {} of {} projects", updated, request.slugs.len()),
+        updated,
+        not_found,
+    }))
+}
+
 /// Delete a development project
 ///
-/// Delete an existing development project
+/// Moves the project to the trash: it disappears from every listing, keeps its slug and
+/// can be brought back with `POST /dev-projects/{slug}/restore`. With `hard=true` the
+/// project (in the trash or not) is deleted for good.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid admin API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 #[utoipa::path(
     delete,
     path = "/dev-projects/{slug}",
     responses(
         (status = 200, description = "Project deleted successfully", body = ProjectOperationResponse),
-        (status = 404, description = "Project not found"),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 403, description = "Forbidden - requires an admin API key", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
-        ("slug" = String, Path, description = "Project slug identifier")
+        ("slug" = String, Path, description = "Project slug identifier"),
+        DeleteQuery
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "Development Projects"
 )]
 pub async fn delete_dev_project(
+    _admin: RequireAdmin,
     State(state): State<AppState>,
     Path(slug): Path<String>,
-) -> Result<Json<ProjectOperationResponse>, StatusCode> {
-    match database::delete_dev_project(&state.db, &slug).await {
-        Ok(true) => Ok(Json(ProjectOperationResponse {
-            message: "Project deleted successfully".to_string(),
-            slug,
-        })),
-        Ok(false) => Err(StatusCode::NOT_FOUND),
+    Query(query): Query<DeleteQuery>,
+) -> Result<Json<ProjectOperationResponse>, ApiError> {
+    let (deleted, message) = if query.hard {
+        (database::purge_dev_project(&state.db, &slug).await, "Project deleted permanently")
+    } else {
+        (database::delete_dev_project(&state.db, &slug).await, "Project moved to the trash")
+    };
+
+    match deleted {
+        Ok(true) => {
+            info!("{}: {}", message, slug);
+            Ok(Json(ProjectOperationResponse {
+                message: message.to_string(),
+                slug,
+            }))
+        }
+        Ok(false) => Err(project_not_found(&slug)),
         Err(e) => {
             error!("Failed to delete dev project: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("Failed to delete dev project"))
         }
     }
 }
+
+/// Restore a deleted development project
+///
+/// Brings a project moved to the trash by `DELETE /dev-projects/{slug}` back.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/dev-projects/{slug}/restore",
+    responses(
+        (status = 200, description = "Project restored", body = ProjectOperationResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "No deleted project with this slug", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Project slug identifier")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Development Projects"
+)]
+pub async fn restore_dev_project(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<ProjectOperationResponse>, ApiError> {
+    match database::restore_dev_project(&state.db, &slug).await {
+        Ok(true) => {
+            info!("Restored project {}", slug);
+            Ok(Json(ProjectOperationResponse {
+                message: "Project restored successfully".to_string(),
+                slug,
+            }))
+        }
+        Ok(false) => Err(ApiError::not_found(
+            "DELETED_PROJECT_NOT_FOUND",
+            format!("No deleted project '{}'", slug),
+        )),
+        Err(e) => {
+            error!("Failed to restore dev project: {}", e);
+            Err(ApiError::internal("Failed to restore dev project"))
+        }
+    }
+}
+
+/// Check the language codes of `translations`, lowercasing them
+fn normalize_translations(
+    translations: BTreeMap<String, ProjectTranslation>,
+) -> Result<BTreeMap<String, ProjectTranslation>, ApiError> {
+    translations
+        .into_iter()
+        .map(|(lang, translation)| Ok((validate_lang(&lang)?, translation)))
+        .collect()
+}
+
+/// Projects fall back to English, so they must have it
+fn require_default_language(translations: &BTreeMap<String, ProjectTranslation>) -> Result<(), ApiError> {
+    if translations.contains_key(DEFAULT_PROJECT_LANGUAGE) {
+        return Ok(());
+    }
+
+    Err(ApiError::bad_request(
+        "MISSING_TRANSLATION",
+        format!("Projects need a '{}' translation", DEFAULT_PROJECT_LANGUAGE),
+    ))
+}
+
+fn invalid_translations(message: String) -> ApiError {
+    ApiError::bad_request("INVALID_TRANSLATIONS", message)
+}
+
+fn project_not_found(slug: &str) -> ApiError {
+    ApiError::not_found("PROJECT_NOT_FOUND", format!("Project '{}' not found", slug))
+}
+
+fn project_already_exists(slug: &str) -> ApiError {
+    ApiError::conflict("PROJECT_ALREADY_EXISTS", format!("Project '{}' already exists", slug))
+}
@@ -0,0 +1,122 @@
+//! Feed Handlers
+//!
+//! This module contains the XML endpoints meant for feed readers and crawlers: the RSS feed
+//! of development projects and the sitemap.
+
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDate;
+use std::fmt::Write;
+use tracing::error;
+use utoipa;
+
+use crate::{database, database::DateRange, error::ApiError, handlers::page_path, AppState};
+
+/// RSS feed of development projects
+///
+/// RSS 2.0 feed of every development project, newest first. Each item uses the English
+/// title and short description, `link` and `date` (as `pubDate`). The channel links to
+/// `SITE_BASE_URL`.
+#[utoipa::path(
+    get,
+    path = "/dev-projects/feed.xml",
+    responses(
+        (status = 200, description = "RSS 2.0 feed", body = String, content_type = "application/rss+xml"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Feeds"
+)]
+pub async fn dev_projects_feed(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let mut projects = database::get_all_dev_projects(&state.db, DateRange::default()).await.map_err(|e| {
+        error!("Failed to fetch dev projects: {}", e);
+        ApiError::internal("Failed to fetch dev projects")
+    })?;
+    projects.sort_by(|a, b| b.date.cmp(&a.date));
+
+    let base = &state.config.site_base_url;
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+    let _ = writeln!(xml, "<title>Development projects</title>");
+    let _ = writeln!(xml, "<link>{}</link>", escape(base));
+    let _ = writeln!(xml, "<description>Latest development projects</description>");
+    if let Some(latest) = projects.first().and_then(|project| rfc2822(&project.date)) {
+        let _ = writeln!(xml, "<lastBuildDate>{}</lastBuildDate>", latest);
+    }
+
+    for project in &projects {
+        let (title, description) = project
+            .translation(&[])
+            .map_or(("", ""), |(_, translation)| (&translation.title, &translation.short_description));
+        xml.push_str("<item>\n");
+        let _ = writeln!(xml, "<title>{}</title>", escape(title));
+        let _ = writeln!(xml, "<link>{}</link>", escape(&project.link));
+        let _ = writeln!(xml, "<description>{}</description>", escape(description));
+        let _ = writeln!(
+            xml,
+            "<guid isPermaLink=\"false\">{}</guid>",
+            escape(&format!("{}{}", base, page_path("/dev-projects", &project.slug)))
+        );
+        if let Some(date) = rfc2822(&project.date) {
+            let _ = writeln!(xml, "<pubDate>{}</pubDate>", date);
+        }
+        xml.push_str("</item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+
+    Ok(([(CONTENT_TYPE, "application/rss+xml; charset=utf-8")], xml).into_response())
+}
+
+/// Sitemap
+///
+/// Sitemap listing the page of every album (`/albums/{slug}`) and development project
+/// (`/dev-projects/{slug}`) under `SITE_BASE_URL`, with its `date` as `<lastmod>`.
+#[utoipa::path(
+    get,
+    path = "/sitemap.xml",
+    responses(
+        (status = 200, description = "Sitemap", body = String, content_type = "application/xml"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Feeds"
+)]
+pub async fn sitemap(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let pages = database::get_page_dates(&state.db).await.map_err(|e| {
+        error!("Failed to fetch sitemap pages: {}", e);
+        ApiError::internal("Failed to fetch sitemap pages")
+    })?;
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (collection, slug, date) in &pages {
+        let url = format!("{}{}", state.config.site_base_url, page_path(&format!("/{}", collection), slug));
+        let _ = writeln!(xml, "<url><loc>{}</loc><lastmod>{}</lastmod></url>", escape(&url), date);
+    }
+    xml.push_str("</urlset>\n");
+
+    Ok(([(CONTENT_TYPE, "application/xml; charset=utf-8")], xml).into_response())
+}
+
+/// Midnight UTC of a `YYYY-MM-DD` date, in the RFC 2822 format RSS uses
+fn rfc2822(date: &str) -> Option<String> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().to_rfc2822())
+}
+
+/// Escape text for use in XML element content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -4,24 +4,47 @@
 //! It handles multipart file uploads, generates thumbnails for images, and manages folder operations.
 
 use axum::{
-    extract::{Multipart, Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Multipart, Path, Query, Request, State},
+    http::{
+        header::{ACCEPT, CACHE_CONTROL, VARY},
+        HeaderMap, HeaderName, HeaderValue,
+    },
+    response::{Json, Response},
+    Extension,
 };
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::path::{Component, PathBuf};
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use utoipa;
-use uuid::Uuid;
 
-use crate::AppState;
+use crate::{
+    database,
+    error::ApiError,
+    etag::serve_with_etag,
+    handlers::validate_slug,
+    media::{
+        file_path, fit_within, generated_from, is_image, is_resizable, process_image, thumbnail_original,
+        thumbnail_path, thumbnail_variant_path, upright_dimensions, webp_path, write_precompressed, write_resized, write_webp_copy, RESIZE_CACHE_DIR,
+    },
+    middleware::{ApiKeyLabel, RequireAdmin},
+    models::{FolderFile, FolderListing, ResizeQuery},
+    uploads::{multipart_error, StagedFile, StagingDir},
+    AppState,
+};
+
+/// Header telling that a response holds a stand-in for a file missing from disk (`thumbnail` or `original`)
+pub(crate) const X_FALLBACK: HeaderName = HeaderName::from_static("x-fallback");
 
 /// Upload files to an album
 ///
 /// Upload one or more files to a specific album. Files are automatically organized by album slug.
 /// Thumbnails are generated for image files.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// The file content is sniffed and must match one of the `ALLOWED_MIME_TYPES`
+/// (JPEG, PNG, GIF and WebP by default), otherwise the request is rejected with 415.
+/// 
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 /// 
 /// Required form fields:
 /// - `slug`: Album identifier (string)
@@ -38,115 +61,104 @@ use crate::AppState;
     ),
     responses(
         (status = 200, description = "Files uploaded successfully", body = UploadResponse),
-        (status = 400, description = "Bad request - no files uploaded or missing slug"),
-        (status = 401, description = "Unauthorized - invalid or missing API key"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Bad request - no files uploaded, missing or invalid slug", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 413, description = "Payload too large - a file or the request exceeds the upload size limit", body = ErrorResponse),
+        (status = 415, description = "Unsupported media type - file content is not in the allowlist", body = ErrorResponse),
+        (status = 429, description = "Too many upload requests from this IP", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "File Management"
 )]
 pub async fn upload_file(
     State(state): State<AppState>,
     mut multipart: Multipart,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let staging = StagingDir::create(&state.config.upload_dir).await?;
     let mut slug: Option<String> = None;
-    let mut file_data: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut staged_files: Vec<StagedFile> = Vec::new();
 
-    // First pass: collect all fields
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error!("Failed to read multipart field: {}", e);
-        StatusCode::BAD_REQUEST
-    })? {
+    // First pass: stream files to the staging directory and collect the other fields
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
         let name = field.name().unwrap_or("");
 
         if name == "slug" {
             let data = field.bytes().await.map_err(|e| {
                 error!("Failed to read slug data: {}", e);
-                StatusCode::BAD_REQUEST
+                ApiError::bad_request("INVALID_MULTIPART", "Failed to read slug field")
             })?;
             slug = Some(String::from_utf8(data.to_vec()).map_err(|e| {
                 error!("Invalid UTF-8 in slug: {}", e);
-                StatusCode::BAD_REQUEST
+                ApiError::bad_request("INVALID_SLUG", "slug must be valid UTF-8")
             })?);
             info!("Received slug: {:?}", slug);
         } else if name == "file" {
             let filename = field.file_name().unwrap_or("unknown").to_string();
-            let data = field.bytes().await.map_err(|e| {
-                error!("Failed to read file data: {}", e);
-                StatusCode::BAD_REQUEST
-            })?;
-            info!("Received file: {}", filename);
-            file_data.push((filename, data.to_vec()));
+            let staged = staging
+                .stage_field(field, filename, state.config.max_upload_bytes, &state.config.allowed_mime_types)
+                .await?;
+            info!("Received file: {} ({}, {} bytes)", staged.filename, staged.mime_type, staged.size);
+            staged_files.push(staged);
         }
     }
 
     // Validate we have both slug and files
     let slug_val = slug.ok_or_else(|| {
         error!("No slug provided");
-        StatusCode::BAD_REQUEST
+        ApiError::bad_request("MISSING_FIELD", "Missing required slug field")
     })?;
+    validate_slug(&slug_val)?;
 
-    if file_data.is_empty() {
+    if staged_files.is_empty() {
         error!("No files provided");
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(ApiError::bad_request("NO_FILES", "At least one file must be provided in the file field"));
     }
 
     // Process uploaded files
     let mut uploaded_files = Vec::new();
     
     // Create slug directory
-    let slug_dir = state.upload_dir.join(&slug_val);
+    let slug_dir = state.config.upload_dir.join(&slug_val);
     fs::create_dir_all(&slug_dir).await.map_err(|e| {
         error!("Failed to create directory {}: {}", slug_dir.display(), e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        ApiError::internal("Failed to create upload directory")
     })?;
 
-    for (filename, data) in file_data {
-        // Generate unique filename
-        let ext = std::path::Path::new(&filename)
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-        
-        let file_stem = std::path::Path::new(&filename)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("file");
-            
-        let unique_filename = format!("{}_{}.{}", 
-            file_stem,
-            Uuid::new_v4().to_string()[..8].to_string(),
-            ext
-        );
-
-        let file_path = slug_dir.join(&unique_filename);
-        
-        // Write file
-        let mut file = fs::File::create(&file_path).await.map_err(|e| {
-            error!("Failed to create file {}: {}", file_path.display(), e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        
-        file.write_all(&data).await.map_err(|e| {
-            error!("Failed to write file {}: {}", file_path.display(), e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        // Generate thumbnail if it's an image
-        if is_image(&filename) {
-            generate_thumbnail(&file_path, &data).await;
+    for mut staged in staged_files {
+        if let Some(quality) = state.config.recompress_quality {
+            staged.recompress(quality).await;
         }
+        let stored = staged.store_in(&slug_dir, state.config.dedupe_uploads).await?;
+
+        // Generate thumbnails and read the size if it's an image
+        let dimensions =
+            process_image(&stored.path, stored.reused, state.config.thumbnail_max_size, &state.config.thumbnail_sizes).await;
 
-        let file_url = format!("/files/{}/{}", slug_val, unique_filename);
+        let file_url = format!("/files/{}/{}", slug_val, stored.stored_name);
+        let webp_url = if state.config.convert_to_webp {
+            write_webp_copy(&stored.path, &file_url, state.config.webp_quality).await
+        } else {
+            None
+        };
+        if state.config.precompress_uploads {
+            write_precompressed(&stored.path).await;
+        }
         uploaded_files.push(serde_json::json!({
-            "filename": unique_filename,
+            "filename": stored.stored_name,
             "url": file_url,
-            "path": file_path.to_string_lossy()
+            "webp_url": webp_url,
+            "width": dimensions.map(|(width, _)| width),
+            "height": dimensions.map(|(_, height)| height),
+            "original_size": stored.original_size,
+            "size": stored.size,
+            "path": stored.path.to_string_lossy()
         }));
 
-        info!("Uploaded file: {} to {}", filename, file_path.display());
+        info!("Uploaded file: {} to {}", stored.filename, stored.path.display());
     }
 
     Ok(Json(serde_json::json!({
@@ -155,12 +167,291 @@ pub async fn upload_file(
     })))
 }
 
+/// Serve an uploaded file
+///
+/// Returns the original file, or with `w` and/or `h` a copy scaled down to fit within those
+/// dimensions (at most 2560 pixels, never larger than the original) for JPEG, PNG and BMP
+/// images. Resized copies are generated on the first request and cached on disk.
+/// `Range`, `ETag` and `Last-Modified` are supported, and `HEAD` returns the headers alone.
+/// The `Content-Type` is set from the file extension.
+///
+/// Clients that send `Accept: image/webp` get the WebP copy written at upload time
+/// (`CONVERT_TO_WEBP`) instead of the original, when there is one.
+///
+/// With `SERVE_THUMBNAIL_FALLBACK`, an image missing from disk is replaced by its largest
+/// thumbnail, and a missing thumbnail by its original, marked with `X-Fallback`.
+#[utoipa::path(
+    get,
+    path = "/files/{path}",
+    responses(
+        (status = 200, description = "File content",
+            headers(("X-Fallback" = String, description = "`thumbnail` or `original` when the requested file is missing and a stand-in is served"))),
+        (status = 206, description = "Requested range of the file"),
+        (status = 304, description = "Not modified since the given ETag"),
+        (status = 400, description = "Invalid dimensions or file cannot be resized", body = ErrorResponse),
+        (status = 404, description = "File not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("path" = String, Path, description = "File path, e.g. `paris-2025/photo_a1b2c3d4.jpg`"),
+        ResizeQuery
+    ),
+    tag = "File Management"
+)]
+pub async fn serve_file(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+    Query(resize): Query<ResizeQuery>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    // Hidden directories hold in-flight uploads and caches, not public files
+    let original = file_path(&state.config.upload_dir, &format!("/files/{}", path))
+        .filter(|_| !path.starts_with('.'))
+        .ok_or_else(|| file_not_found(&path))?;
+
+    if resize.w.is_none() && resize.h.is_none() {
+        if state.config.serve_thumbnail_fallback && !fs::try_exists(&original).await.unwrap_or(true) {
+            if let Some((fallback, kind)) = fallback_for(&original, &state.config.thumbnail_sizes).await {
+                warn!("{} is missing, serving {} instead", original.display(), fallback.display());
+                let mut response = serve_with_etag(&fallback, request).await;
+                response.headers_mut().insert(X_FALLBACK, HeaderValue::from_static(kind));
+                // Not cached for good: the missing file may be restored
+                response.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+                return Ok(response);
+            }
+        }
+        return Ok(serve_original(&original, request).await);
+    }
+
+    if resize.w == Some(0) || resize.h == Some(0) {
+        return Err(ApiError::bad_request("INVALID_DIMENSION", "w and h must be positive numbers of pixels"));
+    }
+    if !is_resizable(&path) {
+        return Err(ApiError::bad_request(
+            "NOT_RESIZABLE",
+            format!("'{}' cannot be resized, only JPEG, PNG and BMP images can", path),
+        ));
+    }
+    if !fs::try_exists(&original).await.unwrap_or(false) {
+        return Err(file_not_found(&path));
+    }
+
+    let dimensions = upright_dimensions(&original)
+        .await
+        .ok_or_else(|| ApiError::internal("Failed to read image"))?;
+    let (width, height) = fit_within(dimensions, resize.w, resize.h);
+    if (width, height) == dimensions {
+        return Ok(serve_with_etag(&original, request).await);
+    }
+
+    // `name.ext` is cached as `<UPLOAD_DIR>/.cache/<folder>/name.<width>x<height>.ext`
+    let relative = std::path::Path::new(&path);
+    let resized = state.config.upload_dir.join(RESIZE_CACHE_DIR).join(relative.with_extension(format!(
+        "{}x{}.{}",
+        width,
+        height,
+        relative.extension().unwrap_or_default().to_string_lossy()
+    )));
+
+    if !fs::try_exists(&resized).await.unwrap_or(false) {
+        write_resized(&original, &resized, width, height).await.map_err(|e| {
+            error!("Failed to resize {} to {}x{}: {}", original.display(), width, height, e);
+            ApiError::internal("Failed to resize image")
+        })?;
+        info!("Cached resized image: {}", resized.display());
+    }
+
+    Ok(serve_with_etag(&resized, request).await)
+}
+
+/// A file to serve in place of the missing `path`, with the `X-Fallback` value telling what it is
+///
+/// A thumbnail stands in for its original, and the original for any of its thumbnails. The
+/// largest thumbnail variant is preferred, then the default thumbnail.
+async fn fallback_for(path: &std::path::Path, thumbnail_sizes: &[u32]) -> Option<(PathBuf, &'static str)> {
+    let file_name = path.file_name()?.to_string_lossy();
+    let (candidates, kind) = match thumbnail_original(&file_name) {
+        Some(original) => (vec![path.with_file_name(original)], "original"),
+        None if is_image(&file_name) => {
+            let variants = thumbnail_sizes.iter().rev().map(|&size| thumbnail_variant_path(path, size));
+            (variants.chain([thumbnail_path(path)]).collect(), "thumbnail")
+        }
+        None => return None,
+    };
+
+    for candidate in candidates {
+        if fs::metadata(&candidate).await.is_ok_and(|metadata| metadata.is_file()) {
+            return Some((candidate, kind));
+        }
+    }
+    None
+}
+
+/// Serve an original file, or its WebP copy to clients that accept WebP
+///
+/// Responses for files that have a WebP copy carry `Vary: Accept`, so caches keep both.
+async fn serve_original(original: &std::path::Path, request: Request) -> Response {
+    let webp = webp_path(original);
+    if !fs::metadata(&webp).await.is_ok_and(|metadata| metadata.is_file()) {
+        return serve_with_etag(original, request).await;
+    }
+
+    let path = if accepts_webp(request.headers()) { webp.as_path() } else { original };
+    let mut response = serve_with_etag(path, request).await;
+    response.headers_mut().append(VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// Whether the `Accept` header explicitly lists `image/webp` with a non-zero quality
+fn accepts_webp(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|range| {
+            let mut params = range.split(';').map(str::trim);
+            let is_webp = params.next().is_some_and(|media_type| media_type.eq_ignore_ascii_case("image/webp"));
+            let quality = params
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            is_webp && quality > 0.0
+        })
+}
+
+fn file_not_found(path: &str) -> ApiError {
+    ApiError::not_found("FILE_NOT_FOUND", format!("File '{}' not found", path))
+}
+
+/// List the files of a folder
+///
+/// Lists the files in an upload folder (thumbnails excluded) with their size and modification
+/// time, and reconciles them with the album of the same slug: `orphaned_files` are on disk but
+/// not referenced by the album (as a photo or its cover), `dangling_records` are album photos
+/// whose file is missing. This is a diagnostic tool, nothing is changed.
+///
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    get,
+    path = "/folder/{slug}",
+    responses(
+        (status = 200, description = "Folder content", body = FolderListing),
+        (status = 400, description = "Invalid folder name or path is not a folder", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 404, description = "Folder not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    params(
+        ("slug" = String, Path, description = "Folder name/slug to list")
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "File Management"
+)]
+pub async fn list_folder(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<FolderListing>, ApiError> {
+    Ok(Json(scan_folder(&state, &slug).await?))
+}
+
+/// Read an upload folder and compare it with the album of the same slug
+async fn scan_folder(state: &AppState, slug: &str) -> Result<FolderListing, ApiError> {
+    let folder_path = folder_path(state, slug)?;
+
+    let album = database::get_album_with_content(&state.db, slug).await.map_err(|e| {
+        error!("Failed to fetch album: {}", e);
+        ApiError::internal("Failed to fetch album")
+    })?;
+    let (cover_url, content) = match album {
+        Some(album) => (Some(album.metadata.preview_img_one_url), album.content),
+        None => (None, Vec::new()),
+    };
+
+    let mut entries = fs::read_dir(&folder_path).await.map_err(|e| {
+        error!("Failed to read folder {}: {}", folder_path.display(), e);
+        ApiError::internal("Failed to read folder")
+    })?;
+
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|e| {
+        error!("Failed to read folder {}: {}", folder_path.display(), e);
+        ApiError::internal("Failed to read folder")
+    })? {
+        let filename = entry.file_name().to_string_lossy().into_owned();
+        let metadata = match entry.metadata().await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => continue,
+        };
+        if generated_from(&filename).is_some() {
+            continue;
+        }
+
+        let url = format!("/files/{}/{}", slug, filename);
+        let in_database = cover_url.as_deref() == Some(url.as_str())
+            || content.iter().any(|photo| photo.img_url == url);
+        files.push(FolderFile {
+            filename,
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(|time| DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)),
+            in_database,
+        });
+    }
+    files.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+    let orphaned_files = files
+        .iter()
+        .filter(|file| !file.in_database)
+        .map(|file| file.filename.clone())
+        .collect();
+
+    let mut dangling_records = Vec::new();
+    for photo in content {
+        if let Some(path) = file_path(&state.config.upload_dir, &photo.img_url) {
+            if !fs::try_exists(&path).await.unwrap_or(false) {
+                dangling_records.push(photo.img_url);
+            }
+        }
+    }
+
+    Ok(FolderListing {
+        folder: slug.to_string(),
+        files,
+        orphaned_files,
+        dangling_records,
+    })
+}
+
+/// Path of the upload folder named `slug`, which must exist and be a directory
+fn folder_path(state: &AppState, slug: &str) -> Result<PathBuf, ApiError> {
+    let mut components = std::path::Path::new(slug).components();
+    if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+        return Err(ApiError::bad_request("INVALID_SLUG", format!("'{}' is not a valid folder name", slug)));
+    }
+
+    let folder_path = state.config.upload_dir.join(slug);
+    if !folder_path.exists() {
+        return Err(ApiError::not_found("FOLDER_NOT_FOUND", format!("Folder '{}' not found", slug)));
+    }
+    if !folder_path.is_dir() {
+        return Err(ApiError::bad_request("NOT_A_DIRECTORY", format!("'{}' is not a folder", slug)));
+    }
+
+    Ok(folder_path)
+}
+
 /// Delete a complete folder and all its contents
 ///
 /// Deletes a folder (typically an album folder) and all files within it.
 /// This operation is irreversible and will permanently remove all files in the specified folder.
 /// 
-/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header.
+/// **Authentication Required**: This endpoint requires a valid admin API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
 /// 
 /// Required headers:
 /// - `X-API-Key`: Valid API key for authentication
@@ -169,40 +460,45 @@ pub async fn upload_file(
     path = "/folder/{slug}",
     responses(
         (status = 200, description = "Folder deleted successfully", body = DeleteResponse),
-        (status = 404, description = "Folder not found"),
-        (status = 401, description = "Unauthorized - invalid or missing API key"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Path is not a folder", body = ErrorResponse),
+        (status = 404, description = "Folder not found", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 403, description = "Forbidden - requires an admin API key", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     params(
         ("slug" = String, Path, description = "Folder name/slug to delete")
     ),
     security(
-        ("api_key" = [])
+        ("api_key" = []),
+        ("bearer_auth" = [])
     ),
     tag = "File Management"
 )]
 pub async fn delete_folder(
+    _admin: RequireAdmin,
     State(state): State<AppState>,
+    Extension(ApiKeyLabel(key_label)): Extension<ApiKeyLabel>,
     Path(slug): Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let folder_path = state.upload_dir.join(&slug);
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let folder_path = state.config.upload_dir.join(&slug);
     
     // Check if folder exists
     if !folder_path.exists() {
         error!("Folder not found: {}", folder_path.display());
-        return Err(StatusCode::NOT_FOUND);
+        return Err(ApiError::not_found("FOLDER_NOT_FOUND", format!("Folder '{}' not found", slug)));
     }
     
     // Check if it's actually a directory
     if !folder_path.is_dir() {
         error!("Path is not a directory: {}", folder_path.display());
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(ApiError::bad_request("NOT_A_DIRECTORY", format!("'{}' is not a folder", slug)));
     }
     
     // Remove the directory and all its contents
     match fs::remove_dir_all(&folder_path).await {
         Ok(_) => {
-            info!("Successfully deleted folder: {}", folder_path.display());
+            info!("Successfully deleted folder: {} (API key: {})", folder_path.display(), key_label);
             Ok(Json(serde_json::json!({
                 "message": "Folder deleted successfully",
                 "folder": slug
@@ -210,37 +506,7 @@ pub async fn delete_folder(
         }
         Err(e) => {
             error!("Failed to delete folder {}: {}", folder_path.display(), e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
-}
-
-/// Check if a file is an image based on its extension
-fn is_image(filename: &str) -> bool {
-    let ext = std::path::Path::new(filename)
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-    
-    matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp")
-}
-
-/// Generate a thumbnail for an image file
-async fn generate_thumbnail(file_path: &std::path::Path, data: &[u8]) {
-    if let Ok(img) = image::load_from_memory(data) {
-        let thumbnail = img.thumbnail(300, 300);
-        
-        let thumb_path = file_path.with_extension(
-            format!("thumb.{}", 
-                file_path.extension().unwrap_or_default().to_str().unwrap_or("jpg")
-            )
-        );
-        
-        if let Err(e) = thumbnail.save(&thumb_path) {
-            error!("Failed to save thumbnail: {}", e);
-        } else {
-            info!("Generated thumbnail: {}", thumb_path.display());
+            Err(ApiError::internal("Failed to delete folder"))
         }
     }
 }
@@ -0,0 +1,130 @@
+//! Health Check Handlers
+//! 
+//! This module contains the unauthenticated health endpoints used by Docker health checks,
+//! load balancers and Kubernetes probes: `/health` reports whether the database connection
+//! pool is usable, `/livez` whether the process is up and `/readyz` whether it can serve
+//! requests (database reachable, upload directory writable).
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use std::{future::Future, time::Duration};
+use tokio::fs;
+use tracing::warn;
+use utoipa;
+use uuid::Uuid;
+
+use crate::{
+    models::{HealthResponse, ReadinessCheck, ReadinessChecks, ReadinessResponse},
+    AppState,
+};
+
+/// How long the database probe (and each readiness check) may take before the service is
+/// reported as degraded
+const DB_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Health check
+///
+/// Runs `SELECT 1` against the database and reports `ok` when it answers within
+/// 2 seconds, `degraded` (with a 503) otherwise.
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "Service is healthy", body = HealthResponse),
+        (status = 503, description = "Database is unreachable or too slow", body = HealthResponse)
+    ),
+    tag = "Health"
+)]
+pub async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let probe = sqlx::query("SELECT 1").execute(&state.db);
+
+    match tokio::time::timeout(DB_PROBE_TIMEOUT, probe).await {
+        Ok(Ok(_)) => (StatusCode::OK, Json(HealthResponse { status: "ok".to_string() })),
+        Ok(Err(e)) => {
+            warn!("Health check failed: {}", e);
+            degraded()
+        }
+        Err(_) => {
+            warn!("Health check timed out after {:?}", DB_PROBE_TIMEOUT);
+            degraded()
+        }
+    }
+}
+
+fn degraded() -> (StatusCode, Json<HealthResponse>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(HealthResponse { status: "degraded".to_string() }),
+    )
+}
+
+/// Liveness probe
+///
+/// Answers `200` as long as the process serves requests, without checking any dependency,
+/// so an unreachable database does not get the container restarted.
+#[utoipa::path(
+    get,
+    path = "/livez",
+    responses(
+        (status = 200, description = "The process is up", body = HealthResponse)
+    ),
+    tag = "Health"
+)]
+pub async fn livez() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok".to_string() })
+}
+
+/// Readiness probe
+///
+/// Runs `SELECT 1` against the database and creates then deletes a file in the upload
+/// directory, both within 2 seconds. Answers `503` with the failed checks when either fails,
+/// so traffic is held back until the service can handle it.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "The service is ready", body = ReadinessResponse),
+        (status = 503, description = "A check failed, see `checks`", body = ReadinessResponse)
+    ),
+    tag = "Health"
+)]
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let database = async { sqlx::query("SELECT 1").execute(&state.db).await.map(|_| ()).map_err(|e| e.to_string()) };
+    let upload_dir = async {
+        let probe = state.config.upload_dir.join(format!(".readyz-{}", Uuid::new_v4()));
+        fs::write(&probe, b"ready").await.map_err(|e| e.to_string())?;
+        fs::remove_file(&probe).await.map_err(|e| e.to_string())
+    };
+    let (database, upload_dir) = tokio::join!(readiness_check("database", database), readiness_check("upload_dir", upload_dir));
+
+    let ready = database.error.is_none() && upload_dir.error.is_none();
+    let (status, label) = if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            status: label.to_string(),
+            checks: ReadinessChecks { database, upload_dir },
+        }),
+    )
+}
+
+/// Run one readiness check, failing it when it takes longer than the database probe timeout
+async fn readiness_check(name: &str, check: impl Future<Output = Result<(), String>>) -> ReadinessCheck {
+    let error = match tokio::time::timeout(DB_PROBE_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e),
+        Err(_) => Some(format!("Timed out after {:?}", DB_PROBE_TIMEOUT)),
+    };
+
+    match error {
+        None => ReadinessCheck { status: "ok".to_string(), error: None },
+        Some(error) => {
+            warn!("Readiness check {} failed: {}", name, error);
+            ReadinessCheck { status: "failed".to_string(), error: Some(error) }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Maintenance Handlers
+//!
+//! This module contains administrative endpoints that repair the upload directory, such as
+//! removing files that no album references anymore.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    Extension,
+};
+use std::{
+    collections::HashSet,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+use tokio::fs;
+use tracing::{error, info, warn};
+use utoipa;
+
+use crate::{
+    database,
+    error::ApiError,
+    media::{generated_from, RESIZE_CACHE_DIR},
+    middleware::{ApiKeyLabel, RequireAdmin},
+    models::*,
+    uploads::STAGING_DIR,
+    AppState,
+};
+
+/// Files modified more recently than this are left alone, as they may belong to an upload
+/// whose album rows are not committed yet
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Delete orphaned files
+///
+/// Walks every folder of the upload directory and deletes the files that no album references
+/// (as a photo or as its cover), together with their thumbnails. Files modified in the last
+/// 10 minutes are kept, since they may belong to an upload still in progress. With
+/// `dry_run=true` nothing is deleted and the response lists what would be.
+///
+/// **Authentication Required**: This endpoint requires a valid admin API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    post,
+    path = "/maintenance/cleanup-orphans",
+    params(CleanupQuery),
+    responses(
+        (status = 200, description = "Orphaned files deleted (or listed for a dry run)", body = CleanupResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 403, description = "Forbidden - requires an admin API key", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "File Management"
+)]
+pub async fn cleanup_orphans(
+    _admin: RequireAdmin,
+    State(state): State<AppState>,
+    Extension(ApiKeyLabel(key_label)): Extension<ApiKeyLabel>,
+    Query(CleanupQuery { dry_run }): Query<CleanupQuery>,
+) -> Result<Json<CleanupResponse>, ApiError> {
+    let referenced = database::get_referenced_file_urls(&state.db).await.map_err(|e| {
+        error!("Failed to fetch referenced files: {}", e);
+        ApiError::internal("Failed to fetch referenced files")
+    })?;
+
+    let referenced: HashSet<String> = referenced.into_iter().collect();
+
+    let orphans = find_orphans(&state.config.upload_dir, &referenced).await.map_err(|e| {
+        error!("Failed to scan upload directory: {}", e);
+        ApiError::internal("Failed to scan upload directory")
+    })?;
+
+    let mut response = CleanupResponse {
+        dry_run,
+        removed_count: 0,
+        removed_bytes: 0,
+        removed: Vec::new(),
+    };
+    for (relative, size) in orphans {
+        if !dry_run {
+            if let Err(e) = fs::remove_file(state.config.upload_dir.join(&relative)).await {
+                warn!("Failed to delete orphaned file {}: {}", relative, e);
+                continue;
+            }
+        }
+
+        response.removed_count += 1;
+        response.removed_bytes += size;
+        response.removed.push(relative);
+    }
+
+    info!(
+        "Orphan cleanup{}: {} files, {} bytes (API key: {})",
+        if dry_run { " (dry run)" } else { "" },
+        response.removed_count,
+        response.removed_bytes,
+        key_label
+    );
+    Ok(Json(response))
+}
+
+/// List the files of every upload folder whose URL is not in `referenced`, as `(folder/filename, size)`
+///
+/// Thumbnails (of any size) and WebP copies live as long as their original is referenced.
+async fn find_orphans(upload_dir: &Path, referenced: &HashSet<String>) -> std::io::Result<Vec<(String, u64)>> {
+    let mut orphans = Vec::new();
+
+    let mut folders = fs::read_dir(upload_dir).await?;
+    while let Some(folder) = folders.next_entry().await? {
+        let folder_name = folder.file_name().to_string_lossy().into_owned();
+        if folder_name == STAGING_DIR || folder_name == RESIZE_CACHE_DIR || !folder.file_type().await?.is_dir() {
+            continue;
+        }
+
+        let mut files = fs::read_dir(folder.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            let metadata = file.metadata().await?;
+            if !metadata.is_file() || recently_modified(&metadata) {
+                continue;
+            }
+
+            let relative = format!("{}/{}", folder_name, file.file_name().to_string_lossy());
+            let url = format!("/files/{}", relative);
+            let original = generated_from(&url).unwrap_or(url);
+            if !referenced.contains(&original) {
+                orphans.push((relative, metadata.len()));
+            }
+        }
+    }
+
+    orphans.sort();
+    Ok(orphans)
+}
+
+fn recently_modified(metadata: &std::fs::Metadata) -> bool {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < ORPHAN_GRACE_PERIOD)
+}
@@ -4,12 +4,361 @@
 //! - `dev_projects` - Development project management endpoints
 //! - `albums` - Photo album management endpoints  
 //! - `files` - File upload and management endpoints
+//! - `health` - Health check endpoint
+//! - `backup` - Content export and import endpoints
+//! - `maintenance` - Upload directory maintenance endpoints
+//! - `stats` - Content statistics endpoint
+//! - `feeds` - RSS feed endpoints
 
 pub mod dev_projects;
 pub mod albums;
 pub mod files;
+pub mod health;
+pub mod backup;
+pub mod maintenance;
+pub mod stats;
+pub mod feeds;
 
 // Re-export all handler functions for easy access
 pub use dev_projects::*;
 pub use albums::*;
 pub use files::*;
+pub use health::*;
+pub use backup::*;
+pub use maintenance::*;
+pub use stats::*;
+pub use feeds::*;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::{
+        header::{ACCEPT, ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_TYPE, LOCATION, VARY},
+        request::Parts,
+        HeaderMap, HeaderName, HeaderValue,
+    },
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::{
+    database::DateRange,
+    error::ApiError,
+    models::{Dev_Project_Metadata, EnvelopeQuery, LangQuery, ListMeta, ListResponse, LocalizedDevProject},
+    API_PREFIX,
+};
+
+/// Header carrying the cursor of the next page of a paginated list
+pub(crate) const X_NEXT_CURSOR: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// Media type asking list endpoints for the [`ListResponse`] envelope
+pub(crate) const ENVELOPE_MEDIA_TYPE: &str = "application/vnd.portfolio.v2+json";
+
+/// Shape of the response of a list endpoint, chosen by the client
+///
+/// Lists are bare JSON arrays unless the request sends `Accept: application/vnd.portfolio.v2+json`
+/// (answered with that content type) or `?envelope=true`, which wrap them in a [`ListResponse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ListFormat {
+    Bare,
+    Envelope { media_type: bool },
+}
+
+impl ListFormat {
+    /// Serialize `items` in this format, with `Vary: Accept` since the shape depends on it
+    pub(crate) fn respond<T: Serialize>(self, items: Vec<T>) -> Response {
+        self.respond_page(items, None)
+    }
+
+    /// Serialize one page of a list, with the cursor of the next page in `meta.next_cursor`
+    /// and the `X-Next-Cursor` header
+    pub(crate) fn respond_page<T: Serialize>(self, items: Vec<T>, next_cursor: Option<String>) -> Response {
+        let cursor_header = next_cursor.as_deref().and_then(|cursor| HeaderValue::from_str(cursor).ok());
+        let mut response = match self {
+            ListFormat::Bare => Json(items).into_response(),
+            ListFormat::Envelope { media_type } => {
+                let count = items.len();
+                let meta = ListMeta { count, next_cursor };
+                let mut response = Json(ListResponse { data: items, meta }).into_response();
+                if media_type {
+                    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(ENVELOPE_MEDIA_TYPE));
+                }
+                response
+            }
+        };
+        if let Some(cursor) = cursor_header {
+            response.headers_mut().insert(X_NEXT_CURSOR, cursor);
+        }
+        response.headers_mut().append(VARY, HeaderValue::from_static("accept"));
+        response
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ListFormat {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let accepts_envelope = parts
+            .headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|range| {
+                range
+                    .split(';')
+                    .next()
+                    .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(ENVELOPE_MEDIA_TYPE))
+            });
+        if accepts_envelope {
+            return Ok(ListFormat::Envelope { media_type: true });
+        }
+
+        let Query(query) = Query::<EnvelopeQuery>::try_from_uri(&parts.uri)
+            .map_err(|_| ApiError::bad_request("INVALID_ENVELOPE", "'envelope' must be true or false"))?;
+        if query.envelope.unwrap_or(false) {
+            Ok(ListFormat::Envelope { media_type: false })
+        } else {
+            Ok(ListFormat::Bare)
+        }
+    }
+}
+
+/// Language of development project responses, chosen by the client
+///
+/// `?lang=` takes precedence over `Accept-Language`. Each project is returned in the first
+/// listed language it has, falling back to English.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProjectLanguage {
+    /// [`LocalizedDevProject`]s, language codes in order of preference
+    Preferred(Vec<String>),
+    /// The full [`Dev_Project_Metadata`] with every translation (`?lang=all`)
+    All,
+}
+
+impl ProjectLanguage {
+    /// Respond with a list of `projects` in this language, in `format`
+    pub(crate) fn respond_list(
+        self,
+        format: ListFormat,
+        projects: Vec<Dev_Project_Metadata>,
+        next_cursor: Option<String>,
+    ) -> Response {
+        match self {
+            ProjectLanguage::Preferred(langs) => {
+                let projects: Vec<LocalizedDevProject> =
+                    projects.into_iter().map(|project| LocalizedDevProject::new(project, &langs)).collect();
+                let lang = common_language(&projects);
+                with_language_headers(format.respond_page(projects, next_cursor), lang)
+            }
+            ProjectLanguage::All => with_language_headers(format.respond_page(projects, next_cursor), None),
+        }
+    }
+
+    /// Respond with `project` in this language
+    pub(crate) fn respond(self, project: Dev_Project_Metadata) -> Response {
+        match self {
+            ProjectLanguage::Preferred(langs) => {
+                let project = LocalizedDevProject::new(project, &langs);
+                let lang = HeaderValue::from_str(&project.lang).ok();
+                with_language_headers(Json(project).into_response(), lang)
+            }
+            ProjectLanguage::All => with_language_headers(Json(project).into_response(), None),
+        }
+    }
+}
+
+/// Language shared by every project of a list, if they are all in the same one
+fn common_language(projects: &[LocalizedDevProject]) -> Option<HeaderValue> {
+    let lang = &projects.first()?.lang;
+    if projects.iter().all(|project| &project.lang == lang) {
+        HeaderValue::from_str(lang).ok()
+    } else {
+        None
+    }
+}
+
+/// Add `Content-Language` and `Vary: Accept-Language`, as the body depends on it
+fn with_language_headers(mut response: Response, lang: Option<HeaderValue>) -> Response {
+    if let Some(lang) = lang {
+        response.headers_mut().insert(CONTENT_LANGUAGE, lang);
+    }
+    response.headers_mut().append(VARY, HeaderValue::from_static("accept-language"));
+    response
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ProjectLanguage {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<LangQuery>::try_from_uri(&parts.uri)
+            .map_err(|_| ApiError::bad_request("INVALID_LANG", "Invalid 'lang' parameter"))?;
+
+        match query.lang.as_deref() {
+            Some(lang) if lang.eq_ignore_ascii_case("all") => Ok(ProjectLanguage::All),
+            Some(lang) => Ok(ProjectLanguage::Preferred(vec![validate_lang(lang)?])),
+            None => Ok(ProjectLanguage::Preferred(accepted_languages(&parts.headers))),
+        }
+    }
+}
+
+/// Check a language code (`en`, `pt-br`...) and return it lowercased
+pub(crate) fn validate_lang(lang: &str) -> Result<String, ApiError> {
+    if is_language_code(lang) {
+        Ok(lang.to_ascii_lowercase())
+    } else {
+        Err(ApiError::bad_request(
+            "INVALID_LANG",
+            format!("'{}' is not a language code like 'en' or 'pt-br'", lang),
+        ))
+    }
+}
+
+/// Two or three letters, optionally followed by `-` and a 2-8 character region or variant
+fn is_language_code(lang: &str) -> bool {
+    let mut parts = lang.splitn(2, '-');
+    let primary = parts.next().unwrap_or_default();
+    let primary_valid = (2..=3).contains(&primary.len()) && primary.bytes().all(|b| b.is_ascii_alphabetic());
+    let subtag_valid = parts
+        .next()
+        .is_none_or(|subtag| (2..=8).contains(&subtag.len()) && subtag.bytes().all(|b| b.is_ascii_alphanumeric()));
+    primary_valid && subtag_valid
+}
+
+/// Languages of the `Accept-Language` header, most preferred first
+///
+/// A regional range (`fr-CA`) is followed by its primary language (`fr`); ranges with a zero
+/// quality, `*` and invalid ones are left out.
+fn accepted_languages(headers: &HeaderMap) -> Vec<String> {
+    let mut ranges: Vec<(String, f32)> = headers
+        .get_all(ACCEPT_LANGUAGE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| {
+            let mut params = range.split(';').map(str::trim);
+            let lang = validate_lang(params.next()?).ok()?;
+            let quality = params
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (quality > 0.0).then_some((lang, quality))
+        })
+        .collect();
+    // Stable, so ties keep the order of the header
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut langs: Vec<String> = Vec::new();
+    for (lang, _) in ranges {
+        let primary = lang.split('-').next().unwrap_or_default().to_string();
+        for lang in [lang, primary] {
+            if !langs.contains(&lang) {
+                langs.push(lang);
+            }
+        }
+    }
+    langs
+}
+
+/// Characters escaped in a URL path segment
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Path of the page of `slug` in `collection` (`/albums`, `/dev-projects`), with the slug escaped
+pub(crate) fn page_path(collection: &str, slug: &str) -> String {
+    format!("{}/{}", collection, utf8_percent_encode(slug, PATH_SEGMENT))
+}
+
+/// Headers of a `201 Created` response, with `Location` pointing at `/api/v1{collection}/{slug}`
+pub(crate) fn created_at(collection: &str, slug: &str) -> HeaderMap {
+    let location = page_path(&format!("{}{}", API_PREFIX, collection), slug);
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&location) {
+        headers.insert(LOCATION, value);
+    }
+    headers
+}
+
+/// Check that `slug` matches `^[a-z0-9]+(?:-[a-z0-9]+)*$`, rejecting it with `400 INVALID_SLUG` otherwise
+pub(crate) fn validate_slug(slug: &str) -> Result<(), ApiError> {
+    let valid = slug
+        .split('-')
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::bad_request(
+            "INVALID_SLUG",
+            format!(
+                "Invalid slug '{}': use lowercase letters and digits separated by single hyphens (^[a-z0-9]+(?:-[a-z0-9]+)*$)",
+                slug
+            ),
+        ))
+    }
+}
+
+/// Derive a slug from a title: diacritics and punctuation are dropped, letters lowercased and
+/// words joined with single hyphens (`"Été à Paris!"` becomes `ete-a-paris`)
+pub(crate) fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    let mut pending_hyphen = false;
+
+    for c in title.nfd().filter(|c| !is_combining_mark(*c)) {
+        if c.is_ascii_alphanumeric() {
+            if pending_hyphen && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_hyphen = false;
+            slug.push(c.to_ascii_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            pending_hyphen = true;
+        }
+    }
+
+    slug
+}
+
+/// Parse a `YYYY-MM-DD` date, rejecting anything else with `400 INVALID_DATE`
+pub(crate) fn parse_date(field: &str, value: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").map_err(|_| {
+        ApiError::bad_request(
+            "INVALID_DATE",
+            format!("Invalid {} '{}': expected a YYYY-MM-DD date", field, value),
+        )
+    })
+}
+
+/// Parse the `from` / `to` query parameters of a listing into an inclusive date range
+pub(crate) fn date_range(from: Option<&str>, to: Option<&str>) -> Result<DateRange, ApiError> {
+    let range = DateRange {
+        from: from.map(|from| parse_date("from", from)).transpose()?,
+        to: to.map(|to| parse_date("to", to)).transpose()?,
+    };
+
+    if let (Some(from), Some(to)) = (range.from, range.to) {
+        if from > to {
+            return Err(ApiError::bad_request(
+                "INVALID_DATE",
+                format!("Invalid date range: from {} is after to {}", from, to),
+            ));
+        }
+    }
+
+    Ok(range)
+}
@@ -0,0 +1,104 @@
+//! Statistics Handler
+//!
+//! This module contains the dashboard endpoint reporting content totals and the disk space
+//! used by the upload directory.
+
+use axum::{extract::State, response::Json};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::error;
+use utoipa;
+
+use crate::{database, error::ApiError, models::*, AppState};
+
+/// How long a measured disk usage is reused before the upload directory is walked again
+const DISK_USAGE_TTL: Duration = Duration::from_secs(60);
+
+/// Last measured size of the upload directory, shared by all requests
+#[derive(Clone, Default)]
+pub struct DiskUsageCache {
+    measured: Arc<Mutex<Option<(Instant, u64)>>>,
+}
+
+impl DiskUsageCache {
+    /// Total size of the files under `upload_dir`, measured at most once per `DISK_USAGE_TTL`
+    async fn get(&self, upload_dir: &Path) -> std::io::Result<u64> {
+        if let Some((measured_at, bytes)) = *self.measured.lock().unwrap() {
+            if measured_at.elapsed() < DISK_USAGE_TTL {
+                return Ok(bytes);
+            }
+        }
+
+        let upload_dir = upload_dir.to_path_buf();
+        let bytes = tokio::task::spawn_blocking(move || directory_size(&upload_dir))
+            .await
+            .map_err(std::io::Error::other)??;
+
+        *self.measured.lock().unwrap() = Some((Instant::now(), bytes));
+        Ok(bytes)
+    }
+}
+
+/// Content statistics
+///
+/// Totals of projects, albums, photos and album categories, and the disk space used by the
+/// upload directory (thumbnails, WebP copies and cached resizes included). The disk usage is
+/// measured at most once a minute.
+///
+/// **Authentication Required**: This endpoint requires a valid API key in the `X-API-Key` header (or `Authorization: Bearer <key>`).
+#[utoipa::path(
+    get,
+    path = "/stats",
+    responses(
+        (status = 200, description = "Content statistics", body = StatsResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = []),
+        ("bearer_auth" = [])
+    ),
+    tag = "Statistics"
+)]
+pub async fn get_stats(State(state): State<AppState>) -> Result<Json<StatsResponse>, ApiError> {
+    let counts = database::count_content(&state.db).await.map_err(|e| {
+        error!("Failed to count content: {}", e);
+        ApiError::internal("Failed to count content")
+    })?;
+
+    let disk_usage_bytes = state.disk_usage.get(&state.config.upload_dir).await.map_err(|e| {
+        error!("Failed to measure upload directory: {}", e);
+        ApiError::internal("Failed to measure upload directory")
+    })?;
+
+    Ok(Json(StatsResponse {
+        dev_projects: counts.dev_projects,
+        albums: counts.albums,
+        photos: counts.photos,
+        categories: counts.categories,
+        disk_usage_bytes,
+    }))
+}
+
+/// Total size of the files under `dir`, walking it recursively without following symlinks
+fn directory_size(dir: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    let mut pending: Vec<PathBuf> = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                total += entry.metadata()?.len();
+            }
+        }
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,192 @@
+//! Idempotency keys for the create routes
+//!
+//! A client retrying a create after a timeout cannot tell whether the first attempt went
+//! through. When it sends the same `Idempotency-Key` header again, the response of the first
+//! successful attempt is replayed (with `Idempotent-Replayed: true`) instead of creating the
+//! content twice or failing with `409 Conflict`. Keys are scoped to the API key and the route,
+//! and kept in memory for `IDEMPOTENCY_TTL_SECS`. Failed attempts are not stored, so they can
+//! be retried with the same key.
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+use crate::{error::ApiError, middleware::ApiKeyLabel};
+
+/// Default time a response is replayed for, in seconds (24 hours)
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Longest accepted `Idempotency-Key`
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Number of stored keys above which expired ones are dropped
+const PRUNE_THRESHOLD: usize = 1024;
+
+static IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+static IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// A successful response, kept to be sent again
+#[derive(Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+struct Entry {
+    /// `None` while the first request with the key is still running
+    response: Option<StoredResponse>,
+    stored_at: Instant,
+}
+
+#[derive(Clone)]
+pub struct IdempotencyCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+enum Lookup {
+    /// First request with this key, the caller runs it
+    New(Pending),
+    /// The key is used by a request that has not finished yet
+    InProgress,
+    /// The key already produced this response
+    Replay(StoredResponse),
+}
+
+/// A request running under a key, which is released when dropped without completing
+struct Pending {
+    cache: IdempotencyCache,
+    key: Option<String>,
+}
+
+impl IdempotencyCache {
+    /// Replay responses for `ttl` after they were sent
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn lookup(&self, key: String) -> Lookup {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if entries.len() > PRUNE_THRESHOLD {
+            entries.retain(|_, entry| entry.response.is_none() || now.duration_since(entry.stored_at) < self.ttl);
+        }
+
+        match entries.get(&key) {
+            Some(Entry { response: None, .. }) => return Lookup::InProgress,
+            Some(Entry { response: Some(response), stored_at }) if now.duration_since(*stored_at) < self.ttl => {
+                return Lookup::Replay(response.clone());
+            }
+            _ => {}
+        }
+
+        entries.insert(key.clone(), Entry { response: None, stored_at: now });
+        Lookup::New(Pending {
+            cache: self.clone(),
+            key: Some(key),
+        })
+    }
+}
+
+impl Pending {
+    /// Store the response of the request, to be replayed for the key from now on
+    fn complete(mut self, response: StoredResponse) {
+        if let Some(key) = self.key.take() {
+            let mut entries = self.cache.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            entries.insert(key, Entry { response: Some(response), stored_at: Instant::now() });
+        }
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            let mut entries = self.cache.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            entries.remove(&key);
+        }
+    }
+}
+
+impl IntoResponse for StoredResponse {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, self.headers, self.body).into_response();
+        response
+            .headers_mut()
+            .insert(IDEMPOTENT_REPLAYED.clone(), HeaderValue::from_static("true"));
+        response
+    }
+}
+
+/// Middleware replaying the stored response of requests sent again with the same `Idempotency-Key`
+///
+/// Requests without the header run as usual. A key still in use by a running request is
+/// answered with `409 Conflict`.
+pub async fn replay_idempotent(State(cache): State<IdempotencyCache>, request: Request, next: Next) -> Response {
+    let Some(value) = request.headers().get(&IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+
+    let key = match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+        _ => {
+            return ApiError::bad_request(
+                "INVALID_IDEMPOTENCY_KEY",
+                format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LENGTH),
+            )
+            .into_response();
+        }
+    };
+    let label = request.extensions().get::<ApiKeyLabel>().map_or("", |label| label.0.as_str());
+    let scoped_key = format!("{} {} {} {}", label, request.method(), request.uri().path(), key);
+
+    let pending = match cache.lookup(scoped_key) {
+        Lookup::New(pending) => pending,
+        Lookup::InProgress => {
+            return ApiError::conflict(
+                "IDEMPOTENCY_KEY_IN_USE",
+                "A request with this Idempotency-Key is still in progress",
+            )
+            .into_response();
+        }
+        Lookup::Replay(response) => {
+            info!("Replaying response of {} {} for Idempotency-Key {}", request.method(), request.uri().path(), key);
+            return response.into_response();
+        }
+    };
+
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to read response to store it: {}", e);
+            return ApiError::internal("Failed to read response").into_response();
+        }
+    };
+    pending.complete(StoredResponse {
+        status: parts.status,
+        headers: parts.headers.clone(),
+        body: body.clone(),
+    });
+
+    Response::from_parts(parts, Body::from(body))
+}
@@ -0,0 +1,315 @@
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderName, HeaderValue, Method},
+    routing::{delete, get, post, put},
+    Router,
+};
+use std::sync::Arc;
+use tower_http::{
+    compression::{
+        predicate::{NotForContentType, Predicate},
+        CompressionLayer, DefaultPredicate,
+    },
+    cors::CorsLayer,
+    trace::TraceLayer,
+};
+use tracing::{info, warn};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+pub mod config;
+mod models;
+mod handlers;
+mod middleware;
+mod error;
+mod etag;
+mod idempotency;
+mod media;
+mod rate_limit;
+mod request_log;
+pub mod request_metrics;
+pub mod uploads;
+mod zip_stream;
+pub mod database;
+
+use handlers::*;
+use models::*;
+use config::Config;
+use sqlx::postgres::PgPool;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handlers::dev_projects::get_dev_projects,
+        handlers::dev_projects::search_dev_projects,
+        handlers::dev_projects::get_dev_project_techs,
+        handlers::feeds::dev_projects_feed,
+        handlers::feeds::sitemap,
+        handlers::dev_projects::get_dev_project,
+        handlers::dev_projects::create_dev_project,
+        handlers::dev_projects::update_dev_project,
+        handlers::dev_projects::rename_dev_project,
+        handlers::dev_projects::reorder_dev_projects,
+        handlers::dev_projects::delete_dev_project,
+        handlers::dev_projects::restore_dev_project,
+        handlers::albums::get_albums,
+        handlers::albums::get_featured_albums,
+        handlers::albums::get_album_categories,
+        handlers::albums::get_recent_photos,
+        handlers::albums::get_popular_albums,
+        handlers::albums::get_albums_batch,
+        handlers::albums::get_random_album,
+        handlers::albums::get_random_photo,
+        handlers::albums::search_albums,
+        handlers::albums::get_album,
+        handlers::albums::download_album,
+        handlers::albums::get_album_photo,
+        handlers::albums::create_album,
+        handlers::albums::create_album_with_files,
+        handlers::albums::update_album,
+        handlers::albums::rename_album,
+        handlers::albums::delete_album,
+        handlers::albums::batch_delete_albums,
+        handlers::albums::restore_album,
+        handlers::albums::add_photos_to_album,
+        handlers::albums::remove_photo_from_album,
+        handlers::albums::reorder_album_photos,
+        handlers::albums::move_photo,
+        handlers::albums::add_photo_tags,
+        handlers::albums::remove_photo_tags,
+        handlers::albums::get_photos_by_tag,
+        handlers::albums::set_album_cover,
+        handlers::files::upload_file,
+        handlers::files::serve_file,
+        handlers::files::list_folder,
+        handlers::files::delete_folder,
+        handlers::maintenance::cleanup_orphans,
+        handlers::health::health,
+        handlers::health::livez,
+        handlers::health::readyz,
+        request_metrics::render,
+        handlers::stats::get_stats,
+        handlers::backup::export_content,
+        handlers::backup::import_content,
+    ),
+    components(
+        schemas(Dev_Project_Metadata, ProjectTranslation, LegacyTranslations, LocalizedDevProject, CreateDevProjectRequest, UpdateDevProjectRequest, ProjectOperationResponse, ReorderProjectsRequest, ReorderProjectsResponse, Album_Metadata, Album_Content, ThumbnailVariant, AlbumWithContent, AlbumPage, PhotoSort, AlbumListItem, AlbumSearchResult, MatchedPhoto, RecentPhoto, CategoryListResponse, TechCount, ListMeta, AlbumList, AlbumMetadataList, AlbumSearchResultList, RecentPhotoList, PhotoList, CategoryList, TechList, DevProjectList, LocalizedDevProjectList, CreateAlbumRequest, UpdateAlbumRequest, RenameRequest, AlbumOperationResponse, AlbumBatchResponse, BatchDeleteRequest, BatchDeleteStatus, BatchDeleteResult, BatchDeleteResponse, CreateAlbumWithFilesFormData, AddPhotosToAlbumFormData, AddPhotosResponse, FailedUpload, RecompressedUpload, RemovePhotoRequest, RemovePhotosResponse, ReorderPhotosRequest, MovePhotoRequest, MovePhotoResponse, PhotoTagsRequest, PhotoTagsResponse, SetCoverRequest, UploadFormData, UploadResponse, UploadedFileInfo, DeleteResponse, FolderFile, FolderListing, CleanupResponse, ErrorResponse, HealthResponse, ReadinessResponse, ReadinessChecks, ReadinessCheck, StatsResponse, ExportDocument, ImportMode, ImportCounts, ImportResponse)
+    ),
+    modifiers(&SecurityAddon, &VersionedPaths),
+    tags(
+        (name = "Development Projects", description = "Portfolio development projects management"),
+        (name = "Photo Albums", description = "Photo albums and gallery management"),
+        (name = "File Management", description = "File upload and management"),
+        (name = "Health", description = "Service health checks"),
+        (name = "Statistics", description = "Content totals for dashboards"),
+        (name = "Feeds", description = "RSS feed and sitemap"),
+        (name = "Backup", description = "Content export and import for backups")
+    ),
+    info(
+        title = "Portfolio API",
+        description = "API for managing portfolio content including development projects and photo albums",
+        version = "0.1.0",
+        contact(
+            name = "Portfolio API Support",
+            email = "support@portfolio.com"
+        ),
+        license(
+            name = "MIT",
+            url = "https://opensource.org/licenses/MIT"
+        )
+    ),
+    servers(
+        (url = "http://127.0.0.1:3000", description = "Local development server"),
+        (url = "/", description = "Production server")
+    )
+)]
+struct ApiDoc;
+
+struct SecurityAddon;
+
+impl utoipa::Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.as_mut().unwrap();
+        components.add_security_scheme(
+            "api_key",
+            utoipa::openapi::security::SecurityScheme::ApiKey(
+                utoipa::openapi::security::ApiKey::Header(
+                    utoipa::openapi::security::ApiKeyValue::new("X-API-Key")
+                )
+            )
+        );
+        components.add_security_scheme(
+            "bearer_auth",
+            utoipa::openapi::security::SecurityScheme::Http(
+                utoipa::openapi::security::Http::new(utoipa::openapi::security::HttpAuthScheme::Bearer)
+            )
+        );
+    }
+}
+
+/// Prefix of the current version of the API routes
+///
+/// `/health`, `/livez`, `/readyz`, `/metrics`, `/sitemap.xml`, `/files` and the Swagger UI are not versioned.
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Prefixes the documented API paths with [`API_PREFIX`], leaving the unversioned ones
+struct VersionedPaths;
+
+impl utoipa::Modify for VersionedPaths {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let paths = std::mem::take(&mut openapi.paths.paths);
+        openapi.paths.paths = paths
+            .into_iter()
+            .map(|(path, item)| {
+                if ["/health", "/livez", "/readyz", "/metrics", "/sitemap.xml", "/files/{path}"].contains(&path.as_str()) {
+                    (path, item)
+                } else {
+                    (format!("{}{}", API_PREFIX, path), item)
+                }
+            })
+            .collect();
+    }
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub db: PgPool,
+    pub config: Arc<Config>,
+    pub disk_usage: handlers::stats::DiskUsageCache,
+}
+
+/// Build the application router, without `/metrics` (mounted by `main`)
+///
+/// Integration tests mount it with a test database and upload directory.
+pub fn app(state: AppState) -> Router {
+    // API keys are parsed once, the middleware only compares them
+    let auth = Arc::new(middleware::AuthConfig::from_config(&state.config));
+
+    // Upload routes reject oversized bodies early, before any field is read
+    let upload_body_limit = DefaultBodyLimit::max(state.config.max_request_bytes as usize);
+
+    // Upload and album creation routes do disk and image work, so they are throttled per IP
+    let upload_rate_limit = axum::middleware::from_fn_with_state(
+        rate_limit::RateLimiter::per_minute(state.config.upload_rate_limit),
+        rate_limit::limit_uploads,
+    );
+
+    // Create routes replay their first response to clients retrying with the same Idempotency-Key
+    let idempotent = axum::middleware::from_fn_with_state(
+        idempotency::IdempotencyCache::new(std::time::Duration::from_secs(state.config.idempotency_ttl_secs)),
+        idempotency::replay_idempotent,
+    );
+
+    // Build our application with routes
+    let protected_routes = Router::new()
+        .route("/upload", post(upload_file).layer(upload_body_limit).layer(upload_rate_limit.clone()))
+        .route("/folder/:slug", get(list_folder).delete(delete_folder))
+        .route("/maintenance/cleanup-orphans", post(cleanup_orphans))
+        .route("/stats", get(get_stats))
+        .route("/dev-projects", post(handlers::dev_projects::create_dev_project).layer(idempotent.clone()))
+        .route("/dev-projects/reorder", put(handlers::dev_projects::reorder_dev_projects))
+        .route("/dev-projects/:slug", put(handlers::dev_projects::update_dev_project))
+        .route("/dev-projects/:slug/rename", post(handlers::dev_projects::rename_dev_project))
+        .route("/dev-projects/:slug", delete(handlers::dev_projects::delete_dev_project))
+        .route("/dev-projects/:slug/restore", post(handlers::dev_projects::restore_dev_project))
+        .route("/albums", post(handlers::albums::create_album).layer(upload_rate_limit.clone()).layer(idempotent.clone()))
+        .route("/albums/with-files", post(handlers::albums::create_album_with_files).layer(upload_body_limit).layer(upload_rate_limit.clone()).layer(idempotent))
+        .route("/albums/batch-delete", post(handlers::albums::batch_delete_albums))
+        .route("/albums/:slug", put(handlers::albums::update_album))
+        .route("/albums/:slug/rename", post(handlers::albums::rename_album))
+        .route("/albums/:slug", delete(handlers::albums::delete_album))
+        .route("/albums/:slug/restore", post(handlers::albums::restore_album))
+        .route("/albums/:slug/photos", put(handlers::albums::add_photos_to_album).layer(upload_body_limit).layer(upload_rate_limit))
+        .route("/albums/:slug/photos", delete(handlers::albums::remove_photo_from_album))
+        .route("/albums/:slug/photos/reorder", put(handlers::albums::reorder_album_photos))
+        .route("/albums/:slug/photos/move", post(handlers::albums::move_photo))
+        .route("/albums/:slug/photos/tags", put(handlers::albums::add_photo_tags).delete(handlers::albums::remove_photo_tags))
+        .route("/albums/:slug/cover", put(handlers::albums::set_album_cover))
+        .route("/export", get(export_content))
+        .route("/import", post(import_content).layer(upload_body_limit))
+        .route_layer(axum::middleware::from_fn_with_state(auth, middleware::api_key_auth));
+
+    let api_routes = Router::new()
+        .route("/dev-projects", get(get_dev_projects))
+        .route("/dev-projects/search", get(search_dev_projects))
+        .route("/dev-projects/techs", get(get_dev_project_techs))
+        .route("/dev-projects/feed.xml", get(dev_projects_feed))
+        .route("/dev-projects/:slug", get(get_dev_project))
+        .route("/albums", get(get_albums))
+        .route("/albums/categories", get(get_album_categories))
+        .route("/albums/featured", get(get_featured_albums))
+        .route("/albums/recent", get(get_recent_photos))
+        .route("/albums/popular", get(get_popular_albums))
+        .route("/albums/batch", get(get_albums_batch))
+        .route("/albums/random", get(get_random_album))
+        .route("/albums/search", get(search_albums))
+        .route("/albums/:slug", get(get_album))
+        .route("/albums/:slug/download", get(download_album))
+        .route("/albums/:slug/photos/:filename", get(get_album_photo))
+        .route("/photos/random", get(get_random_photo))
+        .route("/photos/by-tag/:tag", get(get_photos_by_tag))
+        .merge(protected_routes);
+
+    // Health checks and probes, the sitemap and file URLs (stored in albums) stay at the root
+    Router::new()
+        .route("/health", get(health))
+        .route("/livez", get(livez))
+        .route("/readyz", get(readyz))
+        .route("/sitemap.xml", get(sitemap))
+        .route("/files/*path", get(serve_file).layer(axum::middleware::from_fn(etag::cache_immutable)))
+        .nest(API_PREFIX, api_routes.clone())
+        // Unversioned aliases of the API routes, kept for existing clients
+        .merge(api_routes.route_layer(axum::middleware::from_fn(middleware::deprecated_alias)))
+        .merge(SwaggerUi::new("/swagger-ui")
+            .url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .route_layer(axum::middleware::from_fn(request_metrics::track_metrics))
+        // gzip/brotli for JSON and other text; images (except SVG), videos, ZIP archives and
+        // tiny bodies would not shrink
+        .layer(CompressionLayer::new().compress_when(
+            DefaultPredicate::new()
+                .and(NotForContentType::const_new("video/"))
+                .and(NotForContentType::const_new("application/zip")),
+        ))
+        .layer(cors_layer(&state.config))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_log::make_span)
+                .on_response(request_log::on_response),
+        )
+        .layer(axum::middleware::from_fn(request_log::request_id))
+        .with_state(state)
+}
+
+/// Build the CORS layer from `cors_allowed_origins`, a list of exact origins
+///
+/// Without it every origin is allowed, which is only meant for local development.
+fn cors_layer(config: &Config) -> CorsLayer {
+    let Some(allowed_origins) = &config.cors_allowed_origins else {
+        warn!("CORS_ALLOWED_ORIGINS is not set, allowing requests from any origin");
+        return CorsLayer::permissive();
+    };
+
+    let origins: Vec<HeaderValue> = allowed_origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(origin) => Some(origin),
+            Err(_) => {
+                warn!("Ignoring invalid CORS origin '{}'", origin);
+                None
+            }
+        })
+        .collect();
+    info!("CORS restricted to {} origin(s)", origins.len());
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::IF_MATCH,
+            HeaderName::from_static("x-api-key"),
+            request_log::X_REQUEST_ID,
+        ])
+        .expose_headers([request_log::X_REQUEST_ID, handlers::X_NEXT_CURSOR, handlers::X_FALLBACK, header::ETAG])
+}
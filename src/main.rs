@@ -1,150 +1,106 @@
-use axum::{
-    routing::{delete, get, post, put},
-    Router,
-};
-use std::path::PathBuf;
-use tower_http::{cors::CorsLayer, services::ServeDir};
-use tracing::info;
-use utoipa::OpenApi;
-use utoipa_swagger_ui::SwaggerUi;
-
-mod models;
-mod handlers;
-mod middleware;
-pub mod database;
-
-use handlers::*;
-use models::*;
-use database::init_database;
-use sqlx::postgres::PgPool;
-
-#[derive(OpenApi)]
-#[openapi(
-    paths(
-        handlers::dev_projects::get_dev_projects,
-        handlers::dev_projects::get_dev_project,
-        handlers::dev_projects::create_dev_project,
-        handlers::dev_projects::update_dev_project,
-        handlers::dev_projects::delete_dev_project,
-        handlers::albums::get_albums,
-        handlers::albums::get_album,
-        handlers::albums::create_album,
-        handlers::albums::create_album_with_files,
-        handlers::albums::update_album,
-        handlers::albums::delete_album,
-        handlers::albums::add_photos_to_album,
-        handlers::albums::remove_photo_from_album,
-        handlers::files::upload_file,
-        handlers::files::delete_folder,
-    ),
-    components(
-        schemas(Dev_Project_Metadata, CreateDevProjectRequest, UpdateDevProjectRequest, ProjectOperationResponse, Album_Metadata, Album_Content, AlbumWithContent, CreateAlbumRequest, UpdateAlbumRequest, AlbumOperationResponse, CreateAlbumWithFilesFormData, AddPhotosToAlbumFormData, AddPhotosResponse, RemovePhotoRequest, UploadFormData, UploadResponse, UploadedFileInfo, DeleteResponse)
-    ),
-    modifiers(&SecurityAddon),
-    tags(
-        (name = "Development Projects", description = "Portfolio development projects management"),
-        (name = "Photo Albums", description = "Photo albums and gallery management"),
-        (name = "File Management", description = "File upload and management")
-    ),
-    info(
-        title = "Portfolio API",
-        description = "API for managing portfolio content including development projects and photo albums",
-        version = "0.1.0",
-        contact(
-            name = "Portfolio API Support",
-            email = "support@portfolio.com"
-        ),
-        license(
-            name = "MIT",
-            url = "https://opensource.org/licenses/MIT"
-        )
-    ),
-    servers(
-        (url = "http://127.0.0.1:3000", description = "Local development server"),
-        (url = "/", description = "Production server")
-    )
-)]
-struct ApiDoc;
-
-struct SecurityAddon;
-
-impl utoipa::Modify for SecurityAddon {
-    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
-        let components = openapi.components.as_mut().unwrap();
-        components.add_security_scheme(
-            "api_key",
-            utoipa::openapi::security::SecurityScheme::ApiKey(
-                utoipa::openapi::security::ApiKey::Header(
-                    utoipa::openapi::security::ApiKeyValue::new("X-API-Key")
-                )
-            )
-        );
-    }
-}
+use std::{net::SocketAddr, sync::Arc};
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
 
-#[derive(Clone)]
-pub struct AppState {
-    pub db: PgPool,
-    pub upload_dir: PathBuf,
-}
+use portfolio_server::{app, config::Config, database::init_database, request_metrics, uploads, AppState};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables
     dotenvy::dotenv().ok();
     
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    // Initialize tracing, `RUST_LOG` overrides the default `info` level
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
 
-    // Get configuration from environment or use defaults
-    let host = std::env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let port = std::env::var("SERVER_PORT").unwrap_or_else(|_| "3000".to_string());
-    let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "uploads".to_string());
-    
-    // Create upload directory
-    let upload_dir = PathBuf::from(upload_dir);
-    tokio::fs::create_dir_all(&upload_dir).await?;
+    // Read config.toml and environment overrides once, handlers get it through the state
+    let config = Arc::new(Config::load()?);
+    // A misconfigured server refuses to start rather than failing on the first request
+    config.validate()?;
+
+    // The upload directory was created by `validate`
+    let upload_dir = config.upload_dir.clone();
+    uploads::clear_staging(&upload_dir).await;
+    // Uploads are stored in and `/files` serves from this one directory
+    let resolved = tokio::fs::canonicalize(&upload_dir).await.unwrap_or_else(|_| upload_dir.clone());
+    info!("Storing and serving uploads from {}", resolved.display());
+
+    // Record metrics from the start, before any request or upload
+    let metrics_handle = request_metrics::install_recorder()?;
 
     // Initialize database
-    let db = init_database().await?;
-
-    let state = AppState { db, upload_dir };
-
-    // Build our application with routes
-    let protected_routes = Router::new()
-        .route("/upload", post(upload_file))
-        .route("/folder/:slug", delete(delete_folder))
-        .route("/dev-projects", post(handlers::dev_projects::create_dev_project))
-        .route("/dev-projects/:slug", put(handlers::dev_projects::update_dev_project))
-        .route("/dev-projects/:slug", delete(handlers::dev_projects::delete_dev_project))
-        .route("/albums", post(handlers::albums::create_album))
-        .route("/albums/with-files", post(handlers::albums::create_album_with_files))
-        .route("/albums/:slug", put(handlers::albums::update_album))
-        .route("/albums/:slug", delete(handlers::albums::delete_album))
-        .route("/albums/:slug/photos", put(handlers::albums::add_photos_to_album))
-        .route("/albums/:slug/photos", delete(handlers::albums::remove_photo_from_album))
-        .route_layer(axum::middleware::from_fn(middleware::api_key_auth));
-
-    let app = Router::new()
-        .route("/dev-projects", get(get_dev_projects))
-        .route("/dev-projects/:slug", get(get_dev_project))
-        .route("/albums", get(get_albums))
-        .route("/albums/:slug", get(get_album))
-        .merge(protected_routes)
-        .merge(SwaggerUi::new("/swagger-ui")
-            .url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .nest_service("/files", ServeDir::new("uploads"))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
-
-    let bind_address = format!("{}:{}", host, port);
+    let db = init_database(&config).await?;
+
+    let state = AppState {
+        db,
+        config: config.clone(),
+        disk_usage: Default::default(),
+    };
+
+    let app = app(state);
+
+    // `/metrics` is served by the main listener unless METRICS_ADDR gives it one of its own
+    let app = match &config.metrics_addr {
+        Some(metrics_addr) => {
+            let listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+            info!("Metrics available at http://{}/metrics", metrics_addr);
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(listener, request_metrics::router(metrics_handle)).await {
+                    error!("Metrics server failed: {}", e);
+                }
+            });
+            app
+        }
+        None => app.merge(request_metrics::router(metrics_handle)),
+    };
+
+    let bind_address = format!("{}:{}", config.server_host, config.server_port);
     info!("Server starting on http://{}", bind_address);
     info!("Swagger UI available at http://{}/swagger-ui", bind_address);
     info!("OpenAPI JSON available at http://{}/api-docs/openapi.json", bind_address);
 
-    // Run the server
+    // Run the server until a shutdown signal, letting in-flight requests finish
     let listener = tokio::net::TcpListener::bind(&bind_address).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    uploads::clear_staging(&upload_dir).await;
+    info!("Server stopped");
 
     Ok(())
 }
+
+/// Resolve on Ctrl+C or SIGTERM (`docker stop`, Kubernetes rollouts)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+
+    info!("Waiting for in-flight requests to finish");
+}
@@ -0,0 +1,697 @@
+//! Media helpers shared by the upload handlers
+//!
+//! Image detection and thumbnail generation live here so every upload path
+//! behaves the same way.
+
+use futures_util::future::join_all;
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use tracing::{error, info, warn};
+
+use crate::{error::ApiError, models::ThumbnailVariant};
+
+/// File extensions (lowercase) treated as images
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+/// Default bounding box (in pixels) used when `THUMBNAIL_MAX_SIZE` is not set
+pub const DEFAULT_THUMBNAIL_MAX_SIZE: u32 = 300;
+
+/// Extra thumbnail widths/heights (in pixels) used when `THUMBNAIL_SIZES` is not set
+pub const DEFAULT_THUMBNAIL_SIZES: &[u32] = &[];
+
+/// Default WebP quality (0-100) used when `WEBP_QUALITY` is not set
+pub const DEFAULT_WEBP_QUALITY: u8 = 80;
+
+/// File extensions (lowercase) of the images that get a WebP copy when `CONVERT_TO_WEBP` is on;
+/// GIFs are left alone as the copy would lose their animation
+const WEBP_SOURCE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp"];
+
+/// File extensions (lowercase) of the text files that get `.gz` and `.br` copies when
+/// `PRECOMPRESS_UPLOADS` is on; binary images and videos are already compressed
+const PRECOMPRESSED_EXTENSIONS: &[&str] = &["svg", "json"];
+
+/// Directory, inside the upload directory, caching images resized on request (`/files/...?w=600`)
+pub const RESIZE_CACHE_DIR: &str = ".cache";
+
+/// Largest width/height an image is resized to on request
+pub const MAX_RESIZE_DIMENSION: u32 = 2560;
+
+/// File extensions (lowercase) of the images that can be resized on request; GIFs would
+/// lose their animation and WebP files could only be re-encoded losslessly
+const RESIZABLE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp"];
+
+/// MIME types accepted for uploads when `ALLOWED_MIME_TYPES` is not set
+pub const DEFAULT_ALLOWED_MIME_TYPES: &[&str] = &["image/jpeg", "image/png", "image/gif", "image/webp"];
+
+/// Detect the MIME type of a file from its content rather than its name
+///
+/// Binary formats are recognised by their magic numbers; SVG and JSON are
+/// recognised from their leading text, so only the head of a file is needed.
+pub fn sniff_mime_type(data: &[u8]) -> Option<&'static str> {
+    let brand = data.get(8..12).filter(|_| data.get(4..8) == Some(b"ftyp"));
+
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        Some("image/webp")
+    } else if data.starts_with(b"BM") {
+        Some("image/bmp")
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        Some("image/tiff")
+    } else if let Some(brand) = brand {
+        match brand {
+            b"heic" | b"heix" | b"mif1" | b"msf1" => Some("image/heic"),
+            b"avif" => Some("image/avif"),
+            b"qt  " => Some("video/quicktime"),
+            _ => Some("video/mp4"),
+        }
+    } else if data.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        Some("video/webm")
+    } else if data.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else {
+        sniff_text_mime_type(data)
+    }
+}
+
+fn sniff_text_mime_type(data: &[u8]) -> Option<&'static str> {
+    // `data` may be only the head of the file, so a multi-byte character can be cut off at the end
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&data[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+
+    if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) {
+        Some("image/svg+xml")
+    } else if text.starts_with('{') || text.starts_with('[') {
+        Some("application/json")
+    } else {
+        None
+    }
+}
+
+/// Check that the sniffed content type of an upload is in the allowlist
+///
+/// Allowlist entries are exact MIME types (`image/png`) or type wildcards (`image/*`).
+pub fn check_allowed_type(filename: &str, data: &[u8], allowed: &[String]) -> Result<&'static str, ApiError> {
+    let mime_type = sniff_mime_type(data);
+    let is_allowed = mime_type.is_some_and(|mime_type| {
+        allowed.iter().any(|entry| match entry.strip_suffix("/*") {
+            Some(prefix) => mime_type.split('/').next() == Some(prefix),
+            None => entry == mime_type,
+        })
+    });
+
+    match mime_type {
+        Some(mime_type) if is_allowed => Ok(mime_type),
+        _ => {
+            let detected = mime_type.unwrap_or("unknown");
+            warn!("Rejected upload {} with content type {}", filename, detected);
+            Err(ApiError::unsupported_media_type(format!(
+                "File '{}' has unsupported content type '{}' (allowed: {})",
+                filename,
+                detected,
+                allowed.join(", ")
+            )))
+        }
+    }
+}
+
+/// Content type `/files` serves a file with, from its extension
+///
+/// The formats hosted here are mapped explicitly; anything else falls back to `mime_guess`.
+pub fn content_type_for(path: &Path) -> mime_guess::Mime {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let content_type = match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "heic" => "image/heic",
+        "tif" | "tiff" => "image/tiff",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        _ => return mime_guess::from_path(path).first_or_octet_stream(),
+    };
+    content_type.parse().expect("valid MIME type")
+}
+
+/// Check if a file is an image based on its extension
+pub fn is_image(filename: &str) -> bool {
+    has_extension(filename, IMAGE_EXTENSIONS)
+}
+
+/// Path of the thumbnail generated for `path` (`name.ext` -> `name.thumb.ext`)
+pub fn thumbnail_path(path: &Path) -> PathBuf {
+    path.with_extension(format!(
+        "thumb.{}",
+        path.extension().unwrap_or_default().to_str().unwrap_or("jpg")
+    ))
+}
+
+/// Path of the `size` pixels thumbnail variant of `path` (`name.ext` -> `name.thumb-150.ext`)
+pub fn thumbnail_variant_path(path: &Path, size: u32) -> PathBuf {
+    path.with_extension(format!(
+        "thumb-{}.{}",
+        size,
+        path.extension().unwrap_or_default().to_str().unwrap_or("jpg")
+    ))
+}
+
+/// Public URLs of the thumbnail variants of an uploaded file, one per configured size
+///
+/// Empty for files that do not get a thumbnail, like `thumbnail_url`.
+pub fn thumbnail_variants(img_url: &str, sizes: &[u32]) -> Vec<ThumbnailVariant> {
+    if thumbnail_url(img_url).is_none() {
+        return Vec::new();
+    }
+
+    sizes
+        .iter()
+        .map(|&size| ThumbnailVariant {
+            size,
+            url: thumbnail_variant_path(Path::new(img_url), size).to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+/// Path or URL of the original a thumbnail (or thumbnail variant) was generated from,
+/// `None` if `thumb` is not a thumbnail (`/files/a/b.thumb-150.jpg` -> `/files/a/b.jpg`)
+pub fn thumbnail_original(thumb: &str) -> Option<String> {
+    let (rest, ext) = thumb.rsplit_once('.')?;
+    let (stem, marker) = rest.rsplit_once('.')?;
+    let is_thumbnail = marker == "thumb"
+        || marker
+            .strip_prefix("thumb-")
+            .is_some_and(|size| !size.is_empty() && size.bytes().all(|b| b.is_ascii_digit()));
+
+    is_thumbnail.then(|| format!("{}.{}", stem, ext))
+}
+
+/// Path or URL of the WebP copy of an image (`name.jpg` -> `name.jpg.webp`)
+pub fn webp_path(path: &Path) -> PathBuf {
+    let mut webp = path.as_os_str().to_owned();
+    webp.push(".webp");
+    PathBuf::from(webp)
+}
+
+/// Whether a file gets gzip and brotli copies with `PRECOMPRESS_UPLOADS` (SVG and JSON files)
+pub fn is_precompressible(path: &Path) -> bool {
+    has_extension(&path.to_string_lossy(), PRECOMPRESSED_EXTENSIONS)
+}
+
+/// Paths of the gzip and brotli copies of a file (`name.svg` -> `name.svg.gz`, `name.svg.br`)
+pub fn precompressed_paths(path: &Path) -> [PathBuf; 2] {
+    [".gz", ".br"].map(|suffix| {
+        let mut copy = path.as_os_str().to_owned();
+        copy.push(suffix);
+        PathBuf::from(copy)
+    })
+}
+
+/// Path or URL of the original a generated file (thumbnail, thumbnail variant, WebP copy or
+/// precompressed copy) was made from, `None` for files that are not generated
+pub fn generated_from(path: &str) -> Option<String> {
+    thumbnail_original(path)
+        .or_else(|| {
+            let original = path.strip_suffix(".webp")?;
+            has_extension(original, WEBP_SOURCE_EXTENSIONS).then(|| original.to_string())
+        })
+        .or_else(|| {
+            let original = path.strip_suffix(".gz").or_else(|| path.strip_suffix(".br"))?;
+            has_extension(original, PRECOMPRESSED_EXTENSIONS).then(|| original.to_string())
+        })
+}
+
+fn has_extension(filename: &str, extensions: &[&str]) -> bool {
+    let ext = Path::new(filename)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    extensions.contains(&ext.as_str())
+}
+
+/// Public URL of the thumbnail of an uploaded file (`/files/a/b.jpg` -> `/files/a/b.thumb.jpg`)
+///
+/// `None` for files that do not get a thumbnail (non-images, files not served from `/files`).
+pub fn thumbnail_url(img_url: &str) -> Option<String> {
+    if !img_url.starts_with("/files/") || !is_image(img_url) {
+        return None;
+    }
+
+    Some(thumbnail_path(Path::new(img_url)).to_string_lossy().into_owned())
+}
+
+/// Location on disk of an uploaded file, from its public URL (`/files/a/b.jpg` -> `upload_dir/a/b.jpg`)
+///
+/// `None` for URLs not served from `/files` or trying to leave the upload directory.
+pub fn file_path(upload_dir: &Path, img_url: &str) -> Option<PathBuf> {
+    let relative = Path::new(img_url.strip_prefix("/files/")?);
+    if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+        return None;
+    }
+
+    Some(upload_dir.join(relative))
+}
+
+/// Check if an image can be resized on request, based on its extension
+pub fn is_resizable(filename: &str) -> bool {
+    has_extension(filename, RESIZABLE_EXTENSIONS)
+}
+
+/// Width and height of an image as displayed, i.e. after applying its EXIF orientation
+///
+/// Only the image header is read. `None` when the file is missing or not a readable image.
+pub async fn upright_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let source = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || -> image::ImageResult<(u32, u32)> {
+        let (width, height) = image::image_dimensions(&source)?;
+        Ok(match exif_orientation(&source) {
+            Some(5..=8) => (height, width),
+            _ => (width, height),
+        })
+    })
+    .await;
+
+    match result {
+        Ok(Ok(dimensions)) => Some(dimensions),
+        Ok(Err(e)) => {
+            warn!("Failed to read image size of {}: {}", path.display(), e);
+            None
+        }
+        Err(e) => {
+            error!("Image size task failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Size of an image of `(width, height)` scaled down, keeping its aspect ratio, to fit
+/// within `max_width` x `max_height` (either may be unbounded) and `MAX_RESIZE_DIMENSION`
+///
+/// Images are never scaled up.
+pub fn fit_within((width, height): (u32, u32), max_width: Option<u32>, max_height: Option<u32>) -> (u32, u32) {
+    let max_width = max_width.unwrap_or(u32::MAX).min(MAX_RESIZE_DIMENSION);
+    let max_height = max_height.unwrap_or(u32::MAX).min(MAX_RESIZE_DIMENSION);
+    let scale = (f64::from(max_width) / f64::from(width))
+        .min(f64::from(max_height) / f64::from(height))
+        .min(1.0);
+
+    let scaled = |side: u32| ((f64::from(side) * scale).round() as u32).max(1);
+    (scaled(width), scaled(height))
+}
+
+/// Write `source`, upright and resized to exactly `width` x `height`, to `target`
+///
+/// The image is written to a temporary file first, so concurrent requests never read
+/// a partially written one.
+pub async fn write_resized(source: &Path, target: &Path, width: u32, height: u32) -> image::ImageResult<()> {
+    let source = source.to_path_buf();
+    let target = target.to_path_buf();
+
+    let result = tokio::task::spawn_blocking(move || -> image::ImageResult<()> {
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let format = image::ImageFormat::from_path(&target)?;
+        let temporary = target.with_file_name(format!(".{}.tmp", uuid::Uuid::new_v4()));
+
+        let img = decode_upright(&source)?.resize_exact(width, height, image::imageops::FilterType::Lanczos3);
+        img.save_with_format(&temporary, format)?;
+        std::fs::rename(&temporary, &target)?;
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(e) => Err(image::ImageError::IoError(std::io::Error::other(e))),
+    }
+}
+
+/// Generate the thumbnails of an image file
+///
+/// The image is read back from disk; each thumbnail fits within a square box, keeps the
+/// aspect ratio and is saved next to the original: `name.thumb.ext` for `max`, plus
+/// `name.thumb-<size>.ext` for each of `sizes`, resized concurrently. JPEG and TIFF files
+/// are rotated/flipped according to their EXIF orientation, as browsers do for the original.
+/// Images already fitting a box are copied as that thumbnail instead of being re-encoded
+/// (and enlarged); when they fit every box, the image is not decoded at all.
+///
+/// Returns the width and height of the upright image, `None` if it could not be decoded.
+pub async fn generate_thumbnails(path: &Path, max: u32, sizes: &[u32]) -> Option<(u32, u32)> {
+    let dimensions = upright_dimensions(path).await?;
+    let largest_side = dimensions.0.max(dimensions.1);
+
+    let (copies, resized): (Vec<_>, Vec<_>) = std::iter::once((thumbnail_path(path), max))
+        .chain(sizes.iter().map(|&size| (thumbnail_variant_path(path, size), size)))
+        .partition(|&(_, size)| largest_side <= size);
+
+    for (thumb_path, _) in copies {
+        match tokio::fs::copy(path, &thumb_path).await {
+            Ok(_) => info!("Copied small image as thumbnail: {}", thumb_path.display()),
+            Err(e) => error!("Failed to copy thumbnail: {}", e),
+        }
+    }
+    if resized.is_empty() {
+        return Some(dimensions);
+    }
+
+    let source = path.to_path_buf();
+    let decoded = tokio::task::spawn_blocking(move || decode_upright(&source)).await;
+
+    let img = match decoded {
+        Ok(Ok(img)) => Arc::new(img),
+        Ok(Err(e)) => {
+            error!("Failed to generate thumbnail: {}", e);
+            return None;
+        }
+        Err(e) => {
+            error!("Thumbnail task failed: {}", e);
+            return None;
+        }
+    };
+    let dimensions = (img.width(), img.height());
+
+    let tasks = resized.into_iter().map(|(thumb_path, size)| {
+        let img = Arc::clone(&img);
+        tokio::task::spawn_blocking(move || -> image::ImageResult<PathBuf> {
+            img.thumbnail(size, size).save(&thumb_path)?;
+            Ok(thumb_path)
+        })
+    });
+
+    for result in join_all(tasks).await {
+        match result {
+            Ok(Ok(thumb_path)) => info!("Generated thumbnail: {}", thumb_path.display()),
+            Ok(Err(e)) => error!("Failed to generate thumbnail: {}", e),
+            Err(e) => error!("Thumbnail task failed: {}", e),
+        }
+    }
+
+    Some(dimensions)
+}
+
+/// Generate the thumbnails of a stored upload and return its displayed width and height
+///
+/// `None` for files that are not images. A `reused` file (an identical earlier upload)
+/// already has its thumbnails, so only its size is read.
+pub async fn process_image(path: &Path, reused: bool, max: u32, sizes: &[u32]) -> Option<(u32, u32)> {
+    if !is_image(&path.to_string_lossy()) {
+        return None;
+    }
+
+    if reused {
+        upright_dimensions(path).await
+    } else {
+        generate_thumbnails(path, max, sizes).await
+    }
+}
+
+/// Write a WebP copy of an uploaded image next to it (`name.jpg.webp`) and return its URL
+///
+/// `None` for files that do not get one (non-images, GIFs and WebP files) or when the
+/// conversion fails. A copy left by an earlier upload of the same file is reused.
+pub async fn write_webp_copy(path: &Path, img_url: &str, quality: u8) -> Option<String> {
+    if !has_extension(img_url, WEBP_SOURCE_EXTENSIONS) {
+        return None;
+    }
+    let url = webp_path(Path::new(img_url)).to_string_lossy().into_owned();
+
+    let source = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || -> image::ImageResult<PathBuf> {
+        let webp_path = webp_path(&source);
+        if webp_path.exists() {
+            return Ok(webp_path);
+        }
+
+        let img = decode_upright(&source)?;
+        let encoded = if img.color().has_alpha() {
+            let rgba = img.to_rgba8();
+            webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height()).encode(f32::from(quality))
+        } else {
+            let rgb = img.to_rgb8();
+            webp::Encoder::from_rgb(&rgb, rgb.width(), rgb.height()).encode(f32::from(quality))
+        };
+        std::fs::write(&webp_path, &*encoded)?;
+        Ok(webp_path)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(webp_path)) => {
+            info!("Generated WebP copy: {}", webp_path.display());
+            Some(url)
+        }
+        Ok(Err(e)) => {
+            error!("Failed to convert {} to WebP: {}", path.display(), e);
+            None
+        }
+        Err(e) => {
+            error!("WebP conversion task failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Write gzip and brotli copies of an uploaded SVG or JSON file next to it (`name.svg.gz`,
+/// `name.svg.br`), for `/files` to send to clients accepting those encodings
+///
+/// Other files are skipped, and so is a copy that would not be smaller than the file.
+/// Copies left by an earlier upload of the same file are reused.
+pub async fn write_precompressed(path: &Path) {
+    if !is_precompressible(path) {
+        return;
+    }
+
+    let source = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || -> std::io::Result<Vec<PathBuf>> {
+        let [gz_path, br_path] = precompressed_paths(&source);
+        if gz_path.exists() || br_path.exists() {
+            return Ok(Vec::new());
+        }
+        let data = std::fs::read(&source)?;
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        std::io::Write::write_all(&mut gzip, &data)?;
+        let gzip = gzip.finish()?;
+
+        let mut brotli = Vec::new();
+        let params = brotli::enc::BrotliEncoderParams { quality: 11, ..Default::default() };
+        brotli::BrotliCompress(&mut data.as_slice(), &mut brotli, &params)?;
+
+        let mut written = Vec::new();
+        for (copy_path, copy) in [(gz_path, gzip), (br_path, brotli)] {
+            if copy.len() < data.len() {
+                std::fs::write(&copy_path, copy)?;
+                written.push(copy_path);
+            }
+        }
+        Ok(written)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(written)) => {
+            for copy_path in written {
+                info!("Generated precompressed copy: {}", copy_path.display());
+            }
+        }
+        Ok(Err(e)) => error!("Failed to precompress {}: {}", path.display(), e),
+        Err(e) => error!("Precompression task failed: {}", e),
+    }
+}
+
+/// Re-encode a JPEG file in place at `quality` (1-100), returning its new size
+///
+/// The pixels are kept as they are, so the dimensions do not change, and the EXIF, XMP and
+/// ICC profile segments are copied over (the orientation still applies). `None` when the
+/// file could not be re-encoded or would not get smaller, it is then left untouched.
+pub async fn recompress_jpeg(path: &Path, quality: u8) -> Option<u64> {
+    let source = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || -> image::ImageResult<Option<u64>> {
+        let original = std::fs::read(&source)?;
+        let img = image::load_from_memory_with_format(&original, image::ImageFormat::Jpeg)?;
+
+        let mut encoded = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, quality).encode_image(&img)?;
+        let encoded = with_metadata_segments(&encoded, &original);
+        if encoded.len() >= original.len() {
+            return Ok(None);
+        }
+
+        let temporary = source.with_file_name(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        std::fs::write(&temporary, &encoded)?;
+        std::fs::rename(&temporary, &source)?;
+        Ok(Some(encoded.len() as u64))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(size)) => size,
+        Ok(Err(e)) => {
+            error!("Failed to recompress {}: {}", path.display(), e);
+            None
+        }
+        Err(e) => {
+            error!("Recompression task failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Copy the APP1 (EXIF, XMP) and APP2 (ICC profile) segments of the JPEG `original` into
+/// `encoded`, right after its JFIF header
+fn with_metadata_segments(encoded: &[u8], original: &[u8]) -> Vec<u8> {
+    let segment_len = |data: &[u8], at: usize| data.get(at + 2..at + 4).map(|len| 2 + usize::from(u16::from_be_bytes([len[0], len[1]])));
+
+    let mut segments = Vec::new();
+    let mut at = 2;
+    while original.get(at) == Some(&0xFF) {
+        let marker = original.get(at + 1).copied().unwrap_or_default();
+        let Some(len) = segment_len(original, at).filter(|len| at + len <= original.len()) else {
+            break;
+        };
+        if marker == 0xDA {
+            break;
+        }
+        if marker == 0xE1 || marker == 0xE2 {
+            segments.push(&original[at..at + len]);
+        }
+        at += len;
+    }
+
+    let mut insert_at = 2;
+    if encoded.get(3) == Some(&0xE0) {
+        insert_at += segment_len(encoded, 2).unwrap_or_default();
+    }
+    let mut output = Vec::with_capacity(encoded.len() + segments.iter().map(|segment| segment.len()).sum::<usize>());
+    output.extend_from_slice(&encoded[..insert_at]);
+    segments.into_iter().for_each(|segment| output.extend_from_slice(segment));
+    output.extend_from_slice(&encoded[insert_at..]);
+    output
+}
+
+/// Decode an image file, rotated/flipped according to its EXIF orientation (JPEG and TIFF)
+fn decode_upright(path: &Path) -> image::ImageResult<image::DynamicImage> {
+    let reader = image::io::Reader::open(path)?.with_guessed_format()?;
+    let orientation = match reader.format() {
+        Some(image::ImageFormat::Jpeg | image::ImageFormat::Tiff) => exif_orientation(path),
+        _ => None,
+    };
+    let img = reader.decode()?;
+
+    Ok(match orientation {
+        Some(orientation) => apply_orientation(img, orientation),
+        None => img,
+    })
+}
+
+/// EXIF `Orientation` of an image file, if it has one
+fn exif_orientation(path: &Path) -> Option<u32> {
+    let file = std::fs::File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::BufReader::new(file))
+        .ok()?;
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)
+}
+
+/// Rotate/flip an image stored with EXIF `orientation` (1-8) so it displays upright
+fn apply_orientation(img: image::DynamicImage, orientation: u32) -> image::DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// Camera details read from a photo's EXIF data
+#[derive(Debug, Default)]
+pub struct PhotoExif {
+    /// `Make` and `Model`, e.g. `Canon EOS R5`
+    pub camera: Option<String>,
+    /// `LensModel`
+    pub lens: Option<String>,
+    /// `DateTimeOriginal` as `YYYY-MM-DD`
+    pub date: Option<String>,
+}
+
+/// Read camera, lens and capture date from the EXIF data of an image file
+///
+/// Returns an empty `PhotoExif` when the file has no (readable) EXIF data.
+pub async fn read_exif(path: &Path) -> PhotoExif {
+    let path = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || -> Result<exif::Exif, exif::Error> {
+        let file = std::fs::File::open(&path)?;
+        exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file))
+    })
+    .await;
+
+    let exif = match result {
+        Ok(Ok(exif)) => exif,
+        Ok(Err(e)) => {
+            info!("No EXIF data read: {}", e);
+            return PhotoExif::default();
+        }
+        Err(e) => {
+            error!("EXIF task failed: {}", e);
+            return PhotoExif::default();
+        }
+    };
+
+    let make = exif_ascii(&exif, exif::Tag::Make);
+    let model = exif_ascii(&exif, exif::Tag::Model);
+    let camera = match (make, model) {
+        // Most vendors already repeat the make in the model ("Canon" / "Canon EOS R5")
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    };
+
+    let date = exif
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .and_then(|field| match &field.value {
+            exif::Value::Ascii(values) => values.first(),
+            _ => None,
+        })
+        .and_then(|value| exif::DateTime::from_ascii(value).ok())
+        .map(|dt| format!("{:04}-{:02}-{:02}", dt.year, dt.month, dt.day));
+
+    PhotoExif {
+        camera,
+        lens: exif_ascii(&exif, exif::Tag::LensModel),
+        date,
+    }
+}
+
+fn exif_ascii(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(values) = &field.value else {
+        return None;
+    };
+
+    let value = String::from_utf8_lossy(values.first()?);
+    let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -1,41 +1,180 @@
 use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{
+        header::{AUTHORIZATION, LINK},
+        request::Parts,
+        HeaderMap, HeaderName, HeaderValue,
+    },
     middleware::Next,
     response::Response,
 };
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
 use tracing::warn;
 
+use crate::{config::Config, error::ApiError, request_log, API_PREFIX};
+
+/// Label of the API key that authenticated a request
+///
+/// Inserted into the request extensions by [`api_key_auth`], so handlers can log
+/// which client (CI, CMS, ...) performed an operation.
+#[derive(Debug, Clone)]
+pub struct ApiKeyLabel(pub String);
+
+/// What the API key that authenticated a request is allowed to do
+///
+/// Inserted into the request extensions by [`api_key_auth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScope {
+    /// Create and update content
+    Write,
+    /// Everything `Write` can do, plus deleting albums, projects, photos and folders
+    Admin,
+}
+
+/// Extractor for handlers that require an `Admin` key, rejecting other keys with `403`
+pub struct RequireAdmin;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequireAdmin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<KeyScope>() {
+            Some(KeyScope::Admin) => Ok(RequireAdmin),
+            _ => {
+                warn!("Rejected {} {}: API key is not an admin key", parts.method, parts.uri.path());
+                Err(ApiError::forbidden("This operation requires an admin API key"))
+            }
+        }
+    }
+}
+
+/// Mark responses of the unversioned aliases of the API routes as deprecated
+///
+/// Logs a warning and adds `Deprecation: true` and a `Link` to the `/api/v1` route.
+pub async fn deprecated_alias(request: Request, next: Next) -> Response {
+    let successor = format!("{}{}", API_PREFIX, request.uri().path());
+    warn!("Deprecated route {} {} called, use {}", request.method(), request.uri().path(), successor);
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(DEPRECATION, HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+        response.headers_mut().insert(LINK, link);
+    }
+    response
+}
+
+/// `Deprecation` response header (RFC 9745)
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+struct ConfiguredKey {
+    label: String,
+    key: String,
+    scope: KeyScope,
+}
+
+/// API keys accepted by [`api_key_auth`], parsed once at startup
+pub struct AuthConfig {
+    keys: Vec<ConfiguredKey>,
+}
+
+impl AuthConfig {
+    /// Collect the accepted API keys from the configuration
+    ///
+    /// `admin_api_keys` and `api_keys` are lists of `label:key` or bare `key` entries (bare
+    /// keys are labelled `admin-1`, `key-1`, ...). When `api_keys` is empty, the single
+    /// `api_key` is accepted under the label `default`. Keys from `api_keys` / `api_key` only
+    /// get the `Write` scope when admin keys are configured, otherwise every key is an admin
+    /// key so single-key setups keep working.
+    pub fn from_config(config: &Config) -> Self {
+        let mut keys = key_list(&config.admin_api_keys, "admin", KeyScope::Admin);
+        let scope = if keys.is_empty() { KeyScope::Admin } else { KeyScope::Write };
+
+        let write_keys = key_list(&config.api_keys, "key", scope);
+        if !write_keys.is_empty() {
+            keys.extend(write_keys);
+        } else if let Some(key) = config.api_key.as_deref().map(str::trim).filter(|key| !key.is_empty()) {
+            keys.push(ConfiguredKey {
+                label: "default".to_string(),
+                key: key.to_string(),
+                scope,
+            });
+        }
+
+        Self { keys }
+    }
+
+    /// Whether at least one key is accepted, `Config::validate` refuses to start otherwise
+    pub fn has_keys(&self) -> bool {
+        !self.keys.is_empty()
+    }
+}
+
+fn key_list(entries: &[String], label_prefix: &str, scope: KeyScope) -> Vec<ConfiguredKey> {
+    entries
+        .iter()
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+        .enumerate()
+        .map(|(index, entry)| {
+            let (label, key) = match entry.split_once(':') {
+                Some((label, key)) => (label.trim().to_string(), key.trim().to_string()),
+                None => (format!("{}-{}", label_prefix, index + 1), entry.to_string()),
+            };
+            ConfiguredKey { label, key, scope }
+        })
+        .filter(|configured| !configured.key.is_empty())
+        .collect()
+}
+
+/// Extract the token of an `Authorization: Bearer <token>` header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+
+    scheme.eq_ignore_ascii_case("Bearer").then(|| token.trim())
+}
+
 /// Middleware to check for API key in requests
-/// 
-/// Expects the API key to be provided in the `X-API-Key` header
+///
+/// Expects the API key to be provided in the `X-API-Key` header, or as a bearer
+/// token in the `Authorization` header
 pub async fn api_key_auth(
+    State(auth): State<Arc<AuthConfig>>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // Get the expected API key from environment
-    let expected_api_key = std::env::var("API_KEY")
-        .map_err(|_| {
-            warn!("API_KEY environment variable not set");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    // Get the API key from the request headers
+) -> Result<Response, ApiError> {
+    // Get the API key from the request headers, `X-API-Key` wins over a bearer token
     let api_key = headers
         .get("X-API-Key")
         .and_then(|value| value.to_str().ok())
+        .or_else(|| bearer_token(&headers))
         .ok_or_else(|| {
-            warn!("Missing X-API-Key header");
-            StatusCode::UNAUTHORIZED
+            warn!("Missing X-API-Key or Authorization header");
+            ApiError::unauthorized("Missing API key: send an X-API-Key header or Authorization: Bearer <key>")
         })?;
 
-    // Check if the API key matches
-    if api_key != expected_api_key {
-        warn!("Invalid API key provided");
-        return Err(StatusCode::UNAUTHORIZED);
+    // Check if the API key matches one of the configured keys. Every key is compared in
+    // constant time so the response time does not reveal how much of a key was right.
+    let mut matched = None;
+    for configured in &auth.keys {
+        let is_match = bool::from(api_key.as_bytes().ct_eq(configured.key.as_bytes()));
+        if is_match && matched.is_none() {
+            matched = Some(configured);
+        }
     }
 
+    let Some(ConfiguredKey { label, scope, .. }) = matched else {
+        warn!("Invalid API key provided");
+        return Err(ApiError::unauthorized("Invalid API key"));
+    };
+
     // If API key is valid, continue with the request
+    request_log::record_api_key(label);
+    request.extensions_mut().insert(ApiKeyLabel(label.clone()));
+    request.extensions_mut().insert(*scope);
     Ok(next.run(request).await)
-}
\ No newline at end of file
+}
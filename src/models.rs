@@ -1,30 +1,292 @@
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use std::collections::{BTreeMap, HashMap};
+use utoipa::{IntoParams, ToSchema};
+
+/// Serde helpers for columns stored as comma-separated TEXT (`techs`, `tags`)
+///
+/// Values are exposed as JSON string arrays. On input, either an array of strings or
+/// a legacy comma-separated string is accepted.
+pub mod comma_separated {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ListOrString {
+        List(Vec<String>),
+        String(String),
+    }
+
+    /// Split a stored value into its trimmed, non-empty items
+    pub fn split(value: &str) -> impl Iterator<Item = &str> {
+        value.split(',').map(str::trim).filter(|item| !item.is_empty())
+    }
+
+    pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(split(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        join(ListOrString::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+
+    pub fn deserialize_option<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+        Option::<ListOrString>::deserialize(deserializer)?
+            .map(join)
+            .transpose()
+            .map_err(D::Error::custom)
+    }
+
+    fn join(value: ListOrString) -> Result<String, String> {
+        let items = match value {
+            ListOrString::String(value) => return Ok(split(&value).collect::<Vec<_>>().join(",")),
+            ListOrString::List(items) => items,
+        };
+
+        if let Some(item) = items.iter().find(|item| item.contains(',')) {
+            return Err(format!("list item '{}' must not contain a comma", item));
+        }
+
+        Ok(items
+            .iter()
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .collect::<Vec<_>>()
+            .join(","))
+    }
+}
+
+/// Serde helper for update fields that can be cleared
+///
+/// Used with `#[serde(default)]` on an `Option<Option<T>>`: an omitted field stays `None`
+/// (leave unchanged) while an explicit `null` becomes `Some(None)` (clear the value).
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
     "slug": "portfolio-server",
-    "en_title": "Portfolio Server",
-    "en_short_description": "A lightweight Rust server for portfolio content",
-    "fr_title": "Serveur Portfolio",
-    "fr_short_description": "Un serveur Rust léger pour le contenu de portfolio",
-    "techs": "Rust,Axum,PostgreSQL",
+    "translations": {
+        "en": {
+            "title": "Portfolio Server",
+            "short_description": "A lightweight Rust server for portfolio content"
+        },
+        "fr": {
+            "title": "Serveur Portfolio",
+            "short_description": "Un serveur Rust léger pour le contenu de portfolio"
+        }
+    },
+    "techs": ["Rust", "Axum", "PostgreSQL"],
     "link": "https://github.com/username/portfolio-server",
     "date": "2025-06-13",
-    "tags": "web,backend,api",
-    "priority": 1
+    "tags": ["web", "backend", "api"],
+    "priority": 1,
+    "created_at": "2025-06-13T09:30:00Z",
+    "updated_at": null
 }))]
+#[serde(try_from = "DevProjectDocument")]
+#[allow(non_camel_case_types)]
 pub struct Dev_Project_Metadata {
     pub slug: String,
-    pub en_title: String,
-    pub en_short_description: String,
-    pub fr_title: String,
-    pub fr_short_description: String,
+    /// Title and short description keyed by language code (`en`, `fr`, `es`...)
+    pub translations: BTreeMap<String, ProjectTranslation>,
+    #[serde(with = "comma_separated")]
+    #[schema(value_type = Vec<String>)]
     pub techs: String,
     pub link: String,
+    #[schema(format = Date)]
     pub date: String,
+    #[serde(with = "comma_separated")]
+    #[schema(value_type = Vec<String>)]
     pub tags: String,
     pub priority: i32,
+    /// Creation time (RFC 3339), set by the database
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// Last modification time (RFC 3339), `null` until the first update
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl Dev_Project_Metadata {
+    /// The translation in the first of `langs` the project has, else in English, else any
+    pub fn translation(&self, langs: &[String]) -> Option<(&str, &ProjectTranslation)> {
+        langs
+            .iter()
+            .map(String::as_str)
+            .chain([DEFAULT_PROJECT_LANGUAGE])
+            .find_map(|lang| self.translations.get_key_value(lang))
+            .or_else(|| self.translations.iter().next())
+            .map(|(lang, translation)| (lang.as_str(), translation))
+    }
+}
+
+/// Language projects fall back to when they lack the requested one
+pub const DEFAULT_PROJECT_LANGUAGE: &str = "en";
+
+/// Title and short description of a development project in one language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProjectTranslation {
+    pub title: String,
+    pub short_description: String,
+}
+
+/// The `en_title`, `en_short_description`, `fr_title` and `fr_short_description` fields
+/// projects were translated with before `translations`, still accepted on input
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct LegacyTranslations {
+    /// Same as `translations.en.title`
+    pub en_title: Option<String>,
+    /// Same as `translations.en.short_description`
+    pub en_short_description: Option<String>,
+    /// Same as `translations.fr.title`
+    pub fr_title: Option<String>,
+    /// Same as `translations.fr.short_description`
+    pub fr_short_description: Option<String>,
+}
+
+impl LegacyTranslations {
+    /// Apply the fields to `translations`, patching existing languages
+    ///
+    /// A language the project does not have yet needs both its title and description.
+    pub fn merge_into(self, translations: &mut BTreeMap<String, ProjectTranslation>) -> Result<(), String> {
+        let languages = [
+            ("en", self.en_title, self.en_short_description),
+            ("fr", self.fr_title, self.fr_short_description),
+        ];
+
+        for (lang, title, short_description) in languages {
+            match (translations.get_mut(lang), title, short_description) {
+                (_, None, None) => {}
+                (Some(translation), title, short_description) => {
+                    if let Some(title) = title {
+                        translation.title = title;
+                    }
+                    if let Some(short_description) = short_description {
+                        translation.short_description = short_description;
+                    }
+                }
+                (None, Some(title), Some(short_description)) => {
+                    translations.insert(lang.to_string(), ProjectTranslation { title, short_description });
+                }
+                (None, _, _) => {
+                    return Err(format!("{lang}_title and {lang}_short_description must be sent together"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// [`Dev_Project_Metadata`] as read from requests and import documents, which may use the
+/// [`LegacyTranslations`] fields
+#[derive(Deserialize)]
+struct DevProjectDocument {
+    slug: String,
+    #[serde(default)]
+    translations: BTreeMap<String, ProjectTranslation>,
+    #[serde(flatten)]
+    legacy: LegacyTranslations,
+    #[serde(with = "comma_separated")]
+    techs: String,
+    link: String,
+    date: String,
+    #[serde(with = "comma_separated")]
+    tags: String,
+    priority: i32,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+}
+
+impl TryFrom<DevProjectDocument> for Dev_Project_Metadata {
+    type Error = String;
+
+    fn try_from(document: DevProjectDocument) -> Result<Self, Self::Error> {
+        let mut translations = document.translations;
+        document.legacy.merge_into(&mut translations)?;
+
+        Ok(Self {
+            slug: document.slug,
+            translations,
+            techs: document.techs,
+            link: document.link,
+            date: document.date,
+            tags: document.tags,
+            priority: document.priority,
+            created_at: document.created_at,
+            updated_at: document.updated_at,
+        })
+    }
+}
+
+/// A development project in a single language, with the `title` and `short_description` of
+/// `lang`, returned unless the request asks for `?lang=all`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "slug": "portfolio-server",
+    "lang": "en",
+    "title": "Portfolio Server",
+    "short_description": "A lightweight Rust server for portfolio content",
+    "techs": ["Rust", "Axum", "PostgreSQL"],
+    "link": "https://github.com/username/portfolio-server",
+    "date": "2025-06-13",
+    "tags": ["web", "backend", "api"],
+    "priority": 1,
+    "created_at": "2025-06-13T09:30:00Z",
+    "updated_at": null
+}))]
+pub struct LocalizedDevProject {
+    pub slug: String,
+    /// Language of `title` and `short_description`
+    pub lang: String,
+    pub title: String,
+    pub short_description: String,
+    #[serde(with = "comma_separated")]
+    #[schema(value_type = Vec<String>)]
+    pub techs: String,
+    pub link: String,
+    #[schema(format = Date)]
+    pub date: String,
+    #[serde(with = "comma_separated")]
+    #[schema(value_type = Vec<String>)]
+    pub tags: String,
+    pub priority: i32,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl LocalizedDevProject {
+    /// `project` with its texts in the first of `langs` it has (see [`Dev_Project_Metadata::translation`])
+    pub fn new(mut project: Dev_Project_Metadata, langs: &[String]) -> Self {
+        let lang = project.translation(langs).map(|(lang, _)| lang.to_string()).unwrap_or_default();
+        let translation = project.translations.remove(&lang).unwrap_or(ProjectTranslation {
+            title: String::new(),
+            short_description: String::new(),
+        });
+
+        Self {
+            slug: project.slug,
+            lang,
+            title: translation.title,
+            short_description: translation.short_description,
+            techs: project.techs,
+            link: project.link,
+            date: project.date,
+            tags: project.tags,
+            priority: project.priority,
+            created_at: project.created_at,
+            updated_at: project.updated_at,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -39,20 +301,38 @@ pub struct Dev_Project_Metadata {
     "phone": null,
     "preview_img_one_url": "/files/urban-exploration/preview1.jpg",
     "featured": true,
-    "category": "Street"
+    "category": "Street",
+    "views": 342,
+    "created_at": "2025-06-01T18:00:00Z",
+    "updated_at": "2025-06-02T08:15:00Z"
 }))]
+#[allow(non_camel_case_types)]
 pub struct Album_Metadata {
     pub slug: String,
     pub title: String,
     pub description: String,
     pub short_title: String,
+    #[schema(format = Date)]
     pub date: String,
     pub camera: Option<String>,
     pub lens: Option<String>,
     pub phone: Option<String>,
     pub preview_img_one_url: String,
+    /// Stored `preview_img_one_url`, given by `GET /albums` and `GET /albums/{slug}` where a
+    /// missing cover is replaced by a photo's thumbnail or `DEFAULT_COVER_URL`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_preview_url: Option<String>,
     pub featured: bool,
     pub category: String,
+    /// Number of times the album was viewed, set by the database
+    #[serde(default)]
+    pub views: i64,
+    /// Creation time (RFC 3339), set by the database
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// Last modification time (RFC 3339), `null` until the first update
+    #[serde(default)]
+    pub updated_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -60,11 +340,98 @@ pub struct Album_Metadata {
     "slug": "urban-exploration",
     "img_url": "/files/urban-exploration/street1.jpg",
     "caption": "Street art in downtown",
+    "thumb_url": "/files/urban-exploration/street1.thumb.jpg",
+    "thumbnails": [
+        { "size": 150, "url": "/files/urban-exploration/street1.thumb-150.jpg" },
+        { "size": 400, "url": "/files/urban-exploration/street1.thumb-400.jpg" }
+    ],
+    "webp_url": "/files/urban-exploration/street1.jpg.webp",
+    "width": 1600,
+    "height": 1067,
+    "tags": ["night", "street-art"],
+    "created_at": "2025-06-14T10:12:00Z"
 }))]
+#[allow(non_camel_case_types)]
 pub struct Album_Content {
     pub slug: String,
     pub img_url: String,
     pub caption: String,
+    /// URL of the generated thumbnail, `null` for files without one (non-images)
+    #[serde(default)]
+    pub thumb_url: Option<String>,
+    /// Thumbnail variants for the sizes in `THUMBNAIL_SIZES`, empty for files without thumbnails
+    #[serde(default)]
+    pub thumbnails: Vec<ThumbnailVariant>,
+    /// URL of the WebP copy written at upload time (`CONVERT_TO_WEBP`), `null` when there is none
+    #[serde(default)]
+    pub webp_url: Option<String>,
+    /// Displayed width in pixels (EXIF orientation applied), `null` for non-images
+    #[serde(default)]
+    pub width: Option<u32>,
+    /// Displayed height in pixels (EXIF orientation applied), `null` for non-images
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Tags of the photo, sorted
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Time the photo was added to the album (RFC 3339), set by the database
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+/// A thumbnail of a photo, resized to fit within `size` x `size` pixels
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ThumbnailVariant {
+    pub size: u32,
+    pub url: String,
+}
+
+/// Distinct album categories, sorted alphabetically
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!(["Landscape", "Portrait", "Street"]))]
+pub struct CategoryListResponse(pub Vec<String>);
+
+/// A tech used by development projects, with the number of projects listing it
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({ "name": "Rust", "count": 3 }))]
+pub struct TechCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Envelope of list responses, for clients that ask for it with
+/// `Accept: application/vnd.portfolio.v2+json` or `?envelope=true`
+#[derive(Debug, Serialize, ToSchema)]
+#[aliases(
+    AlbumList = ListResponse<AlbumListItem>,
+    AlbumMetadataList = ListResponse<Album_Metadata>,
+    AlbumSearchResultList = ListResponse<AlbumSearchResult>,
+    RecentPhotoList = ListResponse<RecentPhoto>,
+    PhotoList = ListResponse<Album_Content>,
+    CategoryList = ListResponse<String>,
+    TechList = ListResponse<TechCount>,
+    DevProjectList = ListResponse<Dev_Project_Metadata>,
+    LocalizedDevProjectList = ListResponse<LocalizedDevProject>
+)]
+pub struct ListResponse<T> {
+    pub data: Vec<T>,
+    pub meta: ListMeta,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListMeta {
+    /// Number of items in `data`
+    pub count: usize,
+    /// Pass as `cursor` to get the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Query parameter of the list endpoints
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EnvelopeQuery {
+    /// Wrap the list in `{"data": [...], "meta": {"count": n}}` (`false` by default)
+    pub envelope: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -75,6 +442,58 @@ pub struct AlbumWithContent {
     pub content: Vec<Album_Content>,
 }
 
+/// Album returned by `GET /albums/{slug}`, with one page of its photos
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlbumPage {
+    #[serde(flatten)]
+    #[schema(inline)]
+    pub album: AlbumWithContent,
+    /// Number of photos in the album, regardless of paging
+    #[schema(example = 120)]
+    pub total_photos: i64,
+}
+
+/// Album entry of the album listing, with the number of photos it contains
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlbumListItem {
+    #[serde(flatten)]
+    #[schema(inline)]
+    pub metadata: Album_Metadata,
+    pub photo_count: i64,
+    pub content: Vec<Album_Content>,
+}
+
+/// Album matching a search, with its matching photos
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlbumSearchResult {
+    #[serde(flatten)]
+    #[schema(inline)]
+    pub metadata: Album_Metadata,
+    /// Relevance of the album, results are sorted by descending rank
+    pub rank: f32,
+    /// Photos whose caption matches the search, most relevant first
+    pub matched_photos: Vec<MatchedPhoto>,
+}
+
+/// Photo whose caption matches a search
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MatchedPhoto {
+    #[serde(flatten)]
+    #[schema(inline)]
+    pub content: Album_Content,
+    /// Caption, HTML-escaped, with the matched words wrapped in `<mark>...</mark>`
+    pub highlighted_caption: String,
+}
+
+/// Recently added photo, with the title of its album
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecentPhoto {
+    #[serde(flatten)]
+    #[schema(inline)]
+    pub content: Album_Content,
+    pub album_title: String,
+}
+
 /// Form data for file upload
 /// 
 /// This represents the multipart/form-data structure for uploading files.
@@ -82,6 +501,7 @@ pub struct AlbumWithContent {
 /// - A text input for 'slug' field
 /// - A file picker for 'file' field
 #[derive(ToSchema)]
+#[allow(dead_code)]
 pub struct UploadFormData {
     /// Album slug identifier where the file will be uploaded
     /// 
@@ -121,6 +541,11 @@ pub struct UploadResponse {
 #[schema(example = json!({
     "filename": "photo_a1b2c3d4.jpg",
     "url": "/files/nature-walks/photo_a1b2c3d4.jpg",
+    "webp_url": "/files/nature-walks/photo_a1b2c3d4.jpg.webp",
+    "width": 1600,
+    "height": 1067,
+    "original_size": 20971520,
+    "size": 4194304,
     "path": "/home/user/uploads/nature-walks/photo_a1b2c3d4.jpg"
 }))]
 pub struct UploadedFileInfo {
@@ -130,6 +555,21 @@ pub struct UploadedFileInfo {
     /// Public URL to access the uploaded file
     pub url: String,
     
+    /// Public URL of the WebP copy (`CONVERT_TO_WEBP`), `null` when there is none
+    pub webp_url: Option<String>,
+    
+    /// Displayed width in pixels (EXIF orientation applied), `null` for non-images
+    pub width: Option<u32>,
+    
+    /// Displayed height in pixels (EXIF orientation applied), `null` for non-images
+    pub height: Option<u32>,
+    
+    /// Size of the file as uploaded, in bytes
+    pub original_size: u64,
+    
+    /// Size of the file as stored, in bytes; smaller when it was recompressed (`RECOMPRESS_QUALITY`)
+    pub size: u64,
+    
     /// Full path to the uploaded file on the server
     pub path: String,
 }
@@ -147,50 +587,199 @@ pub struct DeleteResponse {
     pub folder: String,
 }
 
+/// Query parameters for filtering the development project list
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DevProjectFilter {
+    /// Only return projects carrying this tag. Repeat the parameter to match any of several tags.
+    #[serde(default)]
+    pub tag: Vec<String>,
+    /// Only return projects dated on or after this day (`YYYY-MM-DD`)
+    pub from: Option<String>,
+    /// Only return projects dated on or before this day (`YYYY-MM-DD`)
+    pub to: Option<String>,
+    /// Return at most this many projects (all by default)
+    #[param(minimum = 1)]
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page, to continue right after its last project
+    pub cursor: Option<String>,
+    /// Skip this many projects instead of using `cursor` (`0` by default)
+    pub offset: Option<u32>,
+}
+
+/// Language of development project responses
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LangQuery {
+    /// Language code (`en`, `fr`, `es`...) for the texts of that language only, `all` for every
+    /// translation (the full project). Takes precedence over `Accept-Language`; projects without
+    /// the language are returned in English.
+    pub lang: Option<String>,
+}
+
+/// Query parameters for filtering the album list
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlbumFilter {
+    /// Only return albums dated on or after this day (`YYYY-MM-DD`)
+    pub from: Option<String>,
+    /// Only return albums dated on or before this day (`YYYY-MM-DD`)
+    pub to: Option<String>,
+    /// Only return featured (`true`) or not featured (`false`) albums
+    pub featured: Option<bool>,
+}
+
+/// Query parameters for search endpoints
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SearchQuery {
+    /// Text to search for (case-insensitive)
+    pub q: Option<String>,
+}
+
+/// Query parameters of `GET /albums/recent`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RecentQuery {
+    /// Number of photos to return (20 by default, at most 50)
+    pub limit: Option<u32>,
+}
+
+/// Order of the photos returned by `GET /albums/{slug}`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PhotoSort {
+    /// Album order, as set by `PUT /albums/{slug}/photos/reorder`
+    #[default]
+    Position,
+    /// Alphabetical by caption
+    Caption,
+    /// Most recently added first
+    Newest,
+}
+
+/// Query parameters of `GET /albums/{slug}`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlbumQuery {
+    /// Number of photos to return (all by default)
+    pub photo_limit: Option<u32>,
+    /// Number of photos to skip (0 by default)
+    pub photo_offset: Option<u32>,
+    /// Photo order (`position` by default)
+    #[serde(default)]
+    pub sort: PhotoSort,
+    /// Count this request as a view (`true` by default), `false` for admin previews
+    pub track: Option<bool>,
+}
+
+/// Query parameters of `DELETE /albums/{slug}` and `DELETE /dev-projects/{slug}`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteQuery {
+    /// Delete permanently (with the album's files) instead of moving to the trash
+    #[serde(default)]
+    pub hard: bool,
+}
+
+/// Query parameters of `GET /albums/popular`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PopularQuery {
+    /// Number of albums to return (10 by default, at most 50)
+    pub limit: Option<u32>,
+}
+
+/// Query parameters of `GET /albums/batch`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlbumBatchQuery {
+    /// Comma-separated slugs of the albums to return, at most 100
+    #[param(example = "paris-2025,tokyo-2024")]
+    pub slugs: Option<String>,
+}
+
+/// Response of `GET /albums/batch`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "albums": [
+        {
+            "slug": "paris-2025",
+            "title": "Paris 2025",
+            "description": "Photos from Paris",
+            "short_title": "Paris",
+            "date": "2025-06-01",
+            "preview_img_one_url": "/files/paris-2025/photo_a1b2c3d4.jpg",
+            "featured": true,
+            "category": "Travel",
+            "views": 42
+        }
+    ],
+    "missing": ["tokyo-2024"]
+}))]
+pub struct AlbumBatchResponse {
+    /// The albums found, in the requested order
+    pub albums: Vec<Album_Metadata>,
+    /// Requested slugs with no album (or a trashed one)
+    pub missing: Vec<String>,
+}
+
 /// Input data for creating a new development project
+///
+/// Texts go in `translations`, which needs an English (`en`) entry; the `en_*`/`fr_*` fields
+/// are still accepted.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
     "slug": "new-project",
-    "en_title": "New Project",
-    "en_short_description": "A new amazing project",
-    "fr_title": "Nouveau Projet",
-    "fr_short_description": "Un nouveau projet formidable",
-    "techs": "Rust,JavaScript,Python",
+    "translations": {
+        "en": { "title": "New Project", "short_description": "A new amazing project" },
+        "es": { "title": "Nuevo Proyecto", "short_description": "Un nuevo proyecto increíble" }
+    },
+    "techs": ["Rust", "JavaScript", "Python"],
     "link": "https://github.com/username/new-project",
     "date": "2025-06-13",
-    "tags": "web,api,tools",
+    "tags": ["web", "api", "tools"],
     "priority": 1
 }))]
 pub struct CreateDevProjectRequest {
+    #[schema(pattern = "^[a-z0-9]+(?:-[a-z0-9]+)*$")]
     pub slug: String,
-    pub en_title: String,
-    pub en_short_description: String,
-    pub fr_title: String,
-    pub fr_short_description: String,
+    /// Title and short description keyed by language code
+    #[serde(default)]
+    pub translations: BTreeMap<String, ProjectTranslation>,
+    #[serde(flatten)]
+    pub legacy: LegacyTranslations,
+    #[serde(with = "comma_separated")]
+    #[schema(value_type = Vec<String>)]
     pub techs: String,
     pub link: String,
+    #[schema(format = Date)]
     pub date: String,
+    #[serde(with = "comma_separated")]
+    #[schema(value_type = Vec<String>)]
     pub tags: String,
     pub priority: Option<i32>,
 }
 
 /// Input data for updating a development project
 /// All fields are optional - only provided fields will be updated
+///
+/// Languages listed in `translations` are added or replaced, `null` removes one (English
+/// cannot be removed); the `en_*`/`fr_*` fields change a single text.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
-    "en_title": "Updated Project Title",
-    "en_short_description": "Updated project description",
-    "techs": "Rust,TypeScript,React",
+    "translations": {
+        "en": { "title": "Updated Project Title", "short_description": "Updated project description" },
+        "es": null
+    },
+    "techs": ["Rust", "TypeScript", "React"],
     "priority": 2
 }))]
 pub struct UpdateDevProjectRequest {
-    pub en_title: Option<String>,
-    pub en_short_description: Option<String>,
-    pub fr_title: Option<String>,
-    pub fr_short_description: Option<String>,
+    /// Translations to add or replace keyed by language code, `null` to remove one
+    #[schema(value_type = Option<HashMap<String, ProjectTranslation>>)]
+    pub translations: Option<BTreeMap<String, Option<ProjectTranslation>>>,
+    #[serde(flatten)]
+    pub legacy: LegacyTranslations,
+    #[serde(default, deserialize_with = "comma_separated::deserialize_option")]
+    #[schema(value_type = Option<Vec<String>>)]
     pub techs: Option<String>,
     pub link: Option<String>,
+    #[schema(format = Date)]
     pub date: Option<String>,
+    #[serde(default, deserialize_with = "comma_separated::deserialize_option")]
+    #[schema(value_type = Option<Vec<String>>)]
     pub tags: Option<String>,
     pub priority: Option<i32>,
 }
@@ -206,6 +795,38 @@ pub struct ProjectOperationResponse {
     pub slug: String,
 }
 
+/// Request to set the order of the development projects
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "slugs": ["portfolio-server", "weather-app", "cli-tool"],
+    "strict": false
+}))]
+pub struct ReorderProjectsRequest {
+    /// Project slugs in the desired order; their `priority` becomes their position (0, 1, 2, ...)
+    pub slugs: Vec<String>,
+
+    /// Reject the whole request if a slug matches no project, instead of skipping it
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Result of reordering the development projects
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "message": "Reordered 2 of 3 projects",
+    "updated": 2,
+    "not_found": ["cli-tool"]
+}))]
+pub struct ReorderProjectsResponse {
+    pub message: String,
+
+    /// Number of projects whose priority was rewritten
+    pub updated: usize,
+
+    /// Requested slugs that matched no project
+    pub not_found: Vec<String>,
+}
+
 /// Input data for creating a new album
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
@@ -222,35 +843,57 @@ pub struct ProjectOperationResponse {
     "category": "Street"
 }))]
 pub struct CreateAlbumRequest {
-    pub slug: String,
+    /// Derived from `title` when omitted, with `-2`, `-3`, ... appended if that slug is taken
+    #[serde(default)]
+    #[schema(pattern = "^[a-z0-9]+(?:-[a-z0-9]+)*$")]
+    pub slug: Option<String>,
     pub title: String,
     pub description: String,
     pub short_title: String,
-    pub date: String,
+    /// Required, except when creating an album with files whose EXIF data has a capture date
+    #[schema(format = Date)]
+    pub date: Option<String>,
     pub camera: Option<String>,
     pub lens: Option<String>,
     pub phone: Option<String>,
+    /// Cover image: an absolute `http(s)://` URL, the `/files/` URL of an uploaded file,
+    /// or empty for no cover. With `/albums/with-files` it can also be the filename of
+    /// one of the uploaded files.
     pub preview_img_one_url: String,
     pub featured: bool,
     pub category: String,
 }
 
 /// Input data for updating an album
-/// All fields are optional - only provided fields will be updated
+/// All fields are optional - only provided fields will be updated.
+/// `camera`, `lens` and `phone` can be cleared by sending an explicit `null`.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
     "title": "Updated Album Title",
     "description": "Updated album description",
-    "featured": false
+    "featured": false,
+    "phone": null
 }))]
 pub struct UpdateAlbumRequest {
     pub title: Option<String>,
     pub description: Option<String>,
     pub short_title: Option<String>,
+    #[schema(format = Date)]
     pub date: Option<String>,
-    pub camera: Option<String>,
-    pub lens: Option<String>,
-    pub phone: Option<String>,
+    /// Omit to keep, `null` to clear
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, nullable)]
+    pub camera: Option<Option<String>>,
+    /// Omit to keep, `null` to clear
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, nullable)]
+    pub lens: Option<Option<String>>,
+    /// Omit to keep, `null` to clear
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, nullable)]
+    pub phone: Option<Option<String>>,
+    /// Cover image: an absolute `http(s)://` URL, the `/files/` URL of an uploaded file,
+    /// or empty for no cover
     pub preview_img_one_url: Option<String>,
     pub featured: Option<bool>,
     pub category: Option<String>,
@@ -267,16 +910,78 @@ pub struct AlbumOperationResponse {
     pub slug: String,
 }
 
+/// Request to permanently delete several albums
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "slugs": ["import-2025-01", "import-2025-02"]
+}))]
+pub struct BatchDeleteRequest {
+    /// Slugs of the albums to delete
+    pub slugs: Vec<String>,
+}
+
+/// Outcome of deleting one album of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchDeleteStatus {
+    /// The album, its content and its files were deleted
+    Deleted,
+    /// No album (in the trash or not) uses this slug
+    NotFound,
+    /// The album could not be deleted and was left untouched
+    Error,
+}
+
+/// Result of deleting one album of a batch
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchDeleteResult {
+    pub slug: String,
+    pub status: BatchDeleteStatus,
+}
+
+/// Response for `POST /albums/batch-delete`, one result per requested slug, in request order
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "message": "Deleted 1 of 2 albums",
+    "results": [
+        { "slug": "import-2025-01", "status": "deleted" },
+        { "slug": "import-2025-02", "status": "not_found" }
+    ]
+}))]
+pub struct BatchDeleteResponse {
+    pub message: String,
+    pub results: Vec<BatchDeleteResult>,
+}
+
+/// Request to change the slug of an album or development project
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "new_slug": "paris-2025"
+}))]
+pub struct RenameRequest {
+    /// New slug, lowercase letters and digits separated by single hyphens
+    #[schema(pattern = "^[a-z0-9]+(?:-[a-z0-9]+)*$")]
+    pub new_slug: String,
+}
+
 /// Form data for album creation with file upload
 /// 
 /// This represents the multipart/form-data structure for creating an album with files.
 #[derive(ToSchema)]
+#[allow(dead_code)]
 pub struct CreateAlbumWithFilesFormData {
     /// Album metadata as JSON string
     /// 
     /// This should contain all the album information as a JSON object
-    #[schema(example = r#"{"slug":"paris-2025","title":"Paris Street Photography 2025","description":"A collection of street photography","short_title":"Paris 2025","date":"2025-06-13","camera":"Canon EOS R5","lens":"RF 24-70mm f/2.8L","phone":null,"preview_img_one_url":"/files/paris-2025/preview.jpg","featured":true,"category":"Street"}"#)]
+    #[schema(example = r#"{"slug":"paris-2025","title":"Paris Street Photography 2025","description":"A collection of street photography","short_title":"Paris 2025","date":"2025-06-13","camera":"Canon EOS R5","lens":"RF 24-70mm f/2.8L","phone":null,"preview_img_one_url":"eiffel.jpg","featured":true,"category":"Street"}"#)]
     pub album_data: String,
+
+    /// Per-file captions as a JSON string (optional)
+    ///
+    /// Either an array with one caption per file, in upload order, or an object
+    /// mapping filenames to captions. Files without a caption get `Photo from <filename>`.
+    #[schema(example = r#"{"eiffel.jpg":"The Eiffel Tower at dusk","metro.jpg":"Abbesses metro station"}"#)]
+    pub captions: Option<String>,
     
     /// Files to upload with the album
     /// 
@@ -288,6 +993,7 @@ pub struct CreateAlbumWithFilesFormData {
 
 /// Form data for adding photos to an existing album
 #[derive(ToSchema)]
+#[allow(dead_code)]
 pub struct AddPhotosToAlbumFormData {
     /// Caption for the photos (optional)
     /// 
@@ -295,6 +1001,13 @@ pub struct AddPhotosToAlbumFormData {
     /// If not provided, default captions will be generated.
     #[schema(example = "Beautiful sunset view")]
     pub caption: Option<String>,
+
+    /// Per-file captions as a JSON string (optional)
+    ///
+    /// Either an array with one caption per file, in upload order, or an object
+    /// mapping filenames to captions. Files without one fall back to `caption`.
+    #[schema(example = r#"["Sunset over the bay","Boats in the harbour"]"#)]
+    pub captions: Option<String>,
     
     /// Files to upload to the album
     /// 
@@ -303,6 +1016,16 @@ pub struct AddPhotosToAlbumFormData {
     pub files: Vec<u8>,
 }
 
+/// Per-file captions sent in the `captions` multipart field
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PhotoCaptions {
+    /// One caption per file, in upload order
+    List(Vec<String>),
+    /// Captions keyed by the uploaded filename
+    ByFilename(HashMap<String, String>),
+}
+
 /// Response for adding photos to an album
 #[derive(ToSchema, Serialize, Deserialize)]
 #[schema(example = json!({
@@ -313,6 +1036,15 @@ pub struct AddPhotosToAlbumFormData {
             "img_url": "/files/paris-2025/photo_a1b2c3d4.jpg",
             "caption": "Beautiful sunset view"
         }
+    ],
+    "skipped_duplicates": [],
+    "failed": [],
+    "recompressed": [
+        {
+            "img_url": "/files/paris-2025/photo_a1b2c3d4.jpg",
+            "original_size": 20971520,
+            "stored_size": 4194304
+        }
     ]
 }))]
 pub struct AddPhotosResponse {
@@ -324,14 +1056,350 @@ pub struct AddPhotosResponse {
     
     /// List of photos that were added
     pub added_photos: Vec<Album_Content>,
+
+    /// URLs of photos already in the album that uploaded files duplicated, and were skipped
+    /// (only when `DEDUPE_UPLOADS` is on)
+    #[serde(default)]
+    pub skipped_duplicates: Vec<String>,
+
+    /// Uploaded files that could not be stored, in upload order; the other files are still added
+    #[serde(default)]
+    pub failed: Vec<FailedUpload>,
+
+    /// Added photos that were stored recompressed (`RECOMPRESS_QUALITY`), with the space saved
+    #[serde(default)]
+    pub recompressed: Vec<RecompressedUpload>,
+}
+
+/// An uploaded JPEG stored re-encoded at `RECOMPRESS_QUALITY`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecompressedUpload {
+    /// URL of the stored photo
+    pub img_url: String,
+    /// Size of the file as uploaded, in bytes
+    pub original_size: u64,
+    /// Size of the file as stored, in bytes
+    pub stored_size: u64,
+}
+
+/// An uploaded file that could not be stored
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FailedUpload {
+    /// Filename sent by the client
+    pub filename: String,
+    /// Machine readable error code
+    pub code: String,
+    /// Why the file was not stored
+    pub error: String,
+}
+
+/// Request to remove one or several photos from an album
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+#[schema(example = json!({
+    "img_urls": ["/files/paris-2025/photo_a1b2c3d4.jpg", "/files/paris-2025/photo_e5f6a7b8.jpg"]
+}))]
+pub enum RemovePhotoRequest {
+    /// A single photo: `{ "img_url": "..." }`
+    One {
+        /// URL of the image to remove from the album
+        img_url: String,
+    },
+    /// Several photos at once: `{ "img_urls": ["...", "..."] }`
+    Many {
+        /// URLs of the images to remove from the album
+        img_urls: Vec<String>,
+    },
+}
+
+/// Response for removing photos from an album
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "message": "Removed 1 of 2 photos",
+    "slug": "paris-2025",
+    "requested": 2,
+    "removed": 1,
+    "not_found": ["/files/paris-2025/photo_e5f6a7b8.jpg"]
+}))]
+pub struct RemovePhotosResponse {
+    pub message: String,
+    pub slug: String,
+    /// Number of distinct photos in the request
+    pub requested: usize,
+    /// Number of photos actually removed
+    pub removed: usize,
+    /// Requested URLs that were not photos of the album
+    pub not_found: Vec<String>,
 }
 
-/// Request to remove a photo from an album
+/// Tags to add to or remove from a photo
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "img_url": "/files/paris-2025/photo_a1b2c3d4.jpg",
+    "tags": ["sunset", "seine"]
+}))]
+pub struct PhotoTagsRequest {
+    /// URL of the photo in the album
+    pub img_url: String,
+    /// Letters, digits, `-` and `_`, at most 50 characters; stored lowercase, a leading `#` is dropped
+    pub tags: Vec<String>,
+}
+
+/// Tags of a photo after an update
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PhotoTagsResponse {
+    pub slug: String,
+    pub img_url: String,
+    /// All tags of the photo, sorted
+    pub tags: Vec<String>,
+}
+
+/// Request to move a photo to another album
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "img_url": "/files/paris-2025/photo_a1b2c3d4.jpg",
+    "target_slug": "london-2025"
+}))]
+pub struct MovePhotoRequest {
+    /// URL of the photo to move
+    pub img_url: String,
+
+    /// Slug of the album receiving the photo
+    pub target_slug: String,
+}
+
+/// Result of moving a photo to another album
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "message": "Photo moved to album 'london-2025'",
+    "slug": "london-2025",
+    "img_url": "/files/london-2025/photo_a1b2c3d4.jpg"
+}))]
+pub struct MovePhotoResponse {
+    pub message: String,
+
+    /// Slug of the album now holding the photo
+    pub slug: String,
+
+    /// URL of the photo in its new album
+    pub img_url: String,
+}
+
+/// Request to set the cover photo of an album
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
     "img_url": "/files/paris-2025/photo_a1b2c3d4.jpg"
 }))]
-pub struct RemovePhotoRequest {
-    /// URL of the image to remove from the album
+pub struct SetCoverRequest {
+    /// URL of a photo of the album to use as its cover
     pub img_url: String,
 }
+
+/// Request to change the order of the photos in an album
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "img_urls": [
+        "/files/paris-2025/photo_e5f6g7h8.jpg",
+        "/files/paris-2025/photo_a1b2c3d4.jpg"
+    ]
+}))]
+pub struct ReorderPhotosRequest {
+    /// Every image URL of the album, in the desired order
+    pub img_urls: Vec<String>,
+}
+
+/// Error body returned by every failing endpoint
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "error": "Album 'paris-2025' already exists",
+    "code": "ALBUM_ALREADY_EXISTS",
+    "request_id": "6f1c2a4e-8b7d-4e2f-9a31-5c0d2b7e9f10"
+}))]
+pub struct ErrorResponse {
+    /// Human readable description of the error
+    pub error: String,
+
+    /// Stable machine readable error code
+    pub code: String,
+
+    /// ID of the failed request, also sent in the `X-Request-Id` header, to quote in bug reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// On `409 ALBUM_ALREADY_EXISTS` / `PROJECT_ALREADY_EXISTS` from a create, the metadata of
+    /// the album or project already using the slug (absent when it is in the trash)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub existing: Option<serde_json::Value>,
+}
+
+/// Health check status
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({ "status": "ok" }))]
+pub struct HealthResponse {
+    /// `ok` when the database answers, `degraded` otherwise
+    pub status: String,
+}
+
+/// Readiness of the service, as returned by `GET /readyz`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "status": "unavailable",
+    "checks": {
+        "database": { "status": "ok" },
+        "upload_dir": { "status": "failed", "error": "Permission denied (os error 13)" }
+    }
+}))]
+pub struct ReadinessResponse {
+    /// `ok` when every check passed, `unavailable` otherwise
+    pub status: String,
+    pub checks: ReadinessChecks,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessChecks {
+    /// `SELECT 1` answered within 2 seconds
+    pub database: ReadinessCheck,
+    /// A file could be created and deleted in the upload directory
+    pub upload_dir: ReadinessCheck,
+}
+
+/// Outcome of one readiness check
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessCheck {
+    /// `ok` or `failed`
+    pub status: String,
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Content totals, as returned by `GET /stats`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "dev_projects": 12,
+    "albums": 8,
+    "photos": 342,
+    "categories": 4,
+    "disk_usage_bytes": 1073741824
+}))]
+pub struct StatsResponse {
+    pub dev_projects: i64,
+    pub albums: i64,
+
+    /// Photos across all albums
+    pub photos: i64,
+
+    /// Distinct album categories
+    pub categories: i64,
+
+    /// Size of the upload directory, refreshed at most once a minute
+    pub disk_usage_bytes: u64,
+}
+
+/// Full content backup, as returned by `GET /export`
+///
+/// Binary files are not included, only the metadata pointing at them. Album content is
+/// listed in album order.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExportDocument {
+    /// Format version of the document
+    pub version: u32,
+    /// When the export was taken (RFC 3339)
+    pub exported_at: String,
+    pub dev_projects: Vec<Dev_Project_Metadata>,
+    pub albums: Vec<Album_Metadata>,
+    pub album_content: Vec<Album_Content>,
+}
+
+/// How `POST /import` handles projects and albums whose slug already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Keep the existing row (and its photos) and ignore the imported one
+    Skip,
+    /// Replace the existing row with the imported one
+    Overwrite,
+    /// Abort the whole import
+    #[default]
+    Fail,
+}
+
+/// Query parameters of `POST /import`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportQuery {
+    /// What to do with slugs that already exist (`fail` by default)
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+/// Number of imported rows of one kind, by outcome
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ImportCounts {
+    pub created: u64,
+    pub updated: u64,
+    pub skipped: u64,
+}
+
+/// Result of `POST /import`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    pub dev_projects: ImportCounts,
+    pub albums: ImportCounts,
+    pub album_content: ImportCounts,
+}
+
+/// File found in an upload folder
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FolderFile {
+    pub filename: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification time (RFC 3339)
+    pub modified: Option<String>,
+    /// Whether the album of the same slug references the file
+    pub in_database: bool,
+}
+
+/// Files of an upload folder reconciled against the album of the same slug
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FolderListing {
+    /// Name of the folder
+    pub folder: String,
+    /// Files in the folder, thumbnails excluded
+    pub files: Vec<FolderFile>,
+    /// Files on disk that the album does not reference
+    pub orphaned_files: Vec<String>,
+    /// Album photo URLs whose file is missing from disk
+    pub dangling_records: Vec<String>,
+}
+
+/// Query parameters of `GET /files/{path}`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ResizeQuery {
+    /// Maximum width in pixels; the image is scaled down to fit, never up
+    pub w: Option<u32>,
+    /// Maximum height in pixels; the image is scaled down to fit, never up
+    pub h: Option<u32>,
+}
+
+/// Query parameters of `POST /maintenance/cleanup-orphans`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CleanupQuery {
+    /// Only report what would be deleted
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Result of an orphan file cleanup
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CleanupResponse {
+    /// Whether files were only reported, not deleted
+    pub dry_run: bool,
+    /// Number of files (including thumbnails) deleted, or that would be deleted
+    pub removed_count: usize,
+    /// Total size of these files in bytes
+    pub removed_bytes: u64,
+    /// Paths of these files, relative to the upload directory
+    pub removed: Vec<String>,
+}
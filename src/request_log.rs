@@ -0,0 +1,83 @@
+//! Per-request logging
+//!
+//! Every request gets an ID, taken from its `X-Request-Id` header or generated, which is
+//! echoed in the `X-Request-Id` response header and in error bodies. Requests run in a
+//! `request` span carrying that ID, the method and path, plus the label of the API key once
+//! `api_key_auth` accepted it. When the response is sent, its status and latency are logged
+//! at info level inside that span.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue, Response},
+    middleware::Next,
+};
+use std::time::Duration;
+use tracing::{field::Empty, info, info_span, Span};
+use uuid::Uuid;
+
+/// Header carrying the request ID, in requests and responses
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client supplied request ID that is kept, longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    /// ID of the request being handled, so error responses can quote it
+    static REQUEST_ID: String;
+}
+
+/// ID of a request, inserted into the request extensions by [`request_id`]
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Middleware assigning every request an ID and echoing it in the `X-Request-Id` response header
+///
+/// A client supplied `X-Request-Id` of up to 128 visible ASCII characters is kept, otherwise
+/// a UUID is generated.
+pub async fn request_id(mut request: Request, next: Next) -> axum::response::Response {
+    let id = request
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH && id.bytes().all(|b| b.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestId(id.clone()));
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+    response
+}
+
+/// ID of the request being handled, `None` outside of [`request_id`]
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Span of a request, for `TraceLayer::make_span_with`
+pub fn make_span<B>(request: &axum::http::Request<B>) -> Span {
+    let request_id = request.extensions().get::<RequestId>().map(|RequestId(id)| id.as_str());
+    info_span!(
+        "request",
+        id = %request_id.unwrap_or_default(),
+        method = %request.method(),
+        path = %request.uri().path(),
+        api_key = Empty,
+    )
+}
+
+/// Log the status and latency of a response, for `TraceLayer::on_response`
+pub fn on_response<B>(response: &Response<B>, latency: Duration, _span: &Span) {
+    info!(
+        status = response.status().as_u16(),
+        latency_ms = latency.as_millis() as u64,
+        "request completed"
+    );
+}
+
+/// Add the label of the API key that authenticated the current request to its span
+pub fn record_api_key(label: &str) {
+    Span::current().record("api_key", label);
+}
@@ -0,0 +1,86 @@
+//! Prometheus metrics
+//!
+//! Requests are counted and timed by route and status through [`track_metrics`]; uploads
+//! record `uploads_processed_total` and `upload_bytes_written_total` where they are stored.
+//! Everything is rendered in the Prometheus text format by `GET /metrics`, served by the
+//! main listener or, with `METRICS_ADDR`, by a listener of its own.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header::CONTENT_TYPE,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::time::{Duration, Instant};
+
+/// Buckets of the `http_request_duration_seconds` histogram
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// How often the recorder drops samples that are no longer needed
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Install the global Prometheus recorder, before any metric is recorded
+pub fn install_recorder() -> Result<PrometheusHandle, Box<dyn std::error::Error>> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full("http_request_duration_seconds".to_string()), LATENCY_BUCKETS)?
+        .install_recorder()?;
+
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
+
+    Ok(handle)
+}
+
+/// Router serving `GET /metrics`
+pub fn router<S>(handle: PrometheusHandle) -> Router<S> {
+    Router::new().route("/metrics", get(render)).with_state(handle)
+}
+
+/// Prometheus metrics
+///
+/// Request counts (`http_requests_total`) and latencies (`http_request_duration_seconds`) by
+/// method, route and status, plus `uploads_processed_total` and `upload_bytes_written_total`,
+/// in the Prometheus text format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain")
+    ),
+    tag = "Health"
+)]
+pub async fn render(State(handle): State<PrometheusHandle>) -> Response {
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], handle.render()).into_response()
+}
+
+/// Middleware counting and timing requests by method, matched route and status
+pub async fn track_metrics(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+
+    response
+}
@@ -0,0 +1,302 @@
+//! Multipart upload helpers shared by the upload handlers
+//!
+//! Uploaded files are streamed chunk by chunk to a per-request staging directory
+//! (`<UPLOAD_DIR>/.staging/<uuid>`) instead of being buffered in memory. The form
+//! fields that say where a file belongs (`slug`, `album_data`) may arrive after the
+//! files themselves, so files are only moved into their album folder once the whole
+//! request has been read. The staging directory is removed when the request ends,
+//! whether it succeeded or not.
+
+use std::path::{Path, PathBuf};
+
+use axum::{
+    extract::multipart::{Field, MultipartError},
+    http::StatusCode,
+};
+use sha2::{Digest, Sha256};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    media::{check_allowed_type, recompress_jpeg},
+};
+
+/// Default maximum size of a single uploaded file (25 MB)
+pub const DEFAULT_MAX_UPLOAD_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Default maximum size of a whole upload request body (200 MB)
+pub const DEFAULT_MAX_REQUEST_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Default number of files of one upload request processed at the same time
+pub const DEFAULT_UPLOAD_CONCURRENCY: usize = 4;
+
+/// Default maximum number of photos in one album
+pub const DEFAULT_MAX_PHOTOS_PER_ALBUM: usize = 1000;
+
+/// Number of leading bytes kept in memory to sniff the content type of an upload
+const SNIFF_BYTES: usize = 4096;
+
+/// Name of the directory, inside the upload directory, holding in-flight uploads
+pub const STAGING_DIR: &str = ".staging";
+
+/// Convert a multipart error into an API error
+///
+/// Errors caused by the request body limit are reported as `413 Payload Too Large`,
+/// everything else as a malformed request.
+pub fn multipart_error(e: MultipartError) -> ApiError {
+    error!("Failed to read multipart field: {}", e);
+
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        ApiError::payload_too_large("Request body exceeds the maximum upload size")
+    } else {
+        ApiError::bad_request("INVALID_MULTIPART", "Failed to read multipart field")
+    }
+}
+
+/// Build a collision free filename from the client supplied one (`name_1a2b3c4d.ext`)
+pub fn unique_filename(filename: &str) -> String {
+    let path = Path::new(filename);
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+
+    format!("{}_{}.{}", file_stem, &Uuid::new_v4().to_string()[..8], ext)
+}
+
+/// Number of leading hex characters of the SHA-256 used to name deduplicated uploads
+const CONTENT_HASH_CHARS: usize = 12;
+
+/// Build a filename from the client supplied one and the file's content hash (`name_0123456789ab.ext`),
+/// so the same file uploaded twice under the same name is stored once
+pub fn content_filename(filename: &str, content_hash: &str) -> String {
+    let path = Path::new(filename);
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+
+    format!("{}_{}.{}", file_stem, content_hash, ext)
+}
+
+/// A file that has been fully received and validated, waiting in the staging directory
+pub struct StagedFile {
+    /// Filename sent by the client
+    pub filename: String,
+    /// MIME type sniffed from the file content
+    pub mime_type: &'static str,
+    /// Size of the file in bytes, as stored
+    pub size: u64,
+    /// Leading hex characters of the SHA-256 of the content
+    pub content_hash: String,
+    /// Size of the file as it was uploaded, before any recompression
+    pub original_size: u64,
+    path: PathBuf,
+}
+
+/// A staged file moved to its final location
+pub struct StoredFile {
+    /// Filename sent by the client
+    pub filename: String,
+    /// Unique filename the file is stored under
+    pub stored_name: String,
+    /// Full path of the stored file
+    pub path: PathBuf,
+    /// Whether an identical file was already stored under that name, so nothing was written
+    pub reused: bool,
+    /// Size of the file as it was uploaded
+    pub original_size: u64,
+    /// Size of the file as it was stored, smaller than `original_size` when it was recompressed
+    pub size: u64,
+}
+
+impl StagedFile {
+    /// Where the file is staged, for reading it before it is stored
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Re-encode a staged JPEG at `quality` to save disk space (`RECOMPRESS_QUALITY`)
+    ///
+    /// Other formats are left alone: PNG and GIF are lossless, and WebP files could only be
+    /// re-encoded losslessly. `size` is updated when the file got smaller.
+    pub async fn recompress(&mut self, quality: u8) {
+        if self.mime_type != "image/jpeg" {
+            return;
+        }
+
+        if let Some(size) = recompress_jpeg(&self.path, quality).await {
+            info!("Recompressed {} at quality {}: {} -> {} bytes", self.filename, quality, self.size, size);
+            self.size = size;
+        }
+    }
+
+    /// Move the file into `dir` under a unique name
+    ///
+    /// With `dedupe`, the name is derived from the content hash instead, and a file already
+    /// stored under that name is kept rather than overwritten.
+    pub async fn store_in(self, dir: &Path, dedupe: bool) -> Result<StoredFile, ApiError> {
+        self.store_for(dir, dir, dedupe).await
+    }
+
+    /// Move the file into `dir`, to be moved on to `target` once it is accepted
+    ///
+    /// Like `store_in`, except that with `dedupe` a file already stored in `target` under
+    /// the same name is reused in place, and nothing is written.
+    pub async fn store_for(self, dir: &Path, target: &Path, dedupe: bool) -> Result<StoredFile, ApiError> {
+        let stored_name = if dedupe {
+            content_filename(&self.filename, &self.content_hash)
+        } else {
+            unique_filename(&self.filename)
+        };
+
+        let existing = target.join(&stored_name);
+        if dedupe && fs::try_exists(&existing).await.unwrap_or(false) {
+            info!("{} is already stored as {}", self.filename, existing.display());
+            metrics::counter!("uploads_processed_total", "outcome" => "reused").increment(1);
+            return Ok(StoredFile {
+                filename: self.filename,
+                stored_name,
+                path: existing,
+                reused: true,
+                original_size: self.original_size,
+                size: self.size,
+            });
+        }
+
+        let path = dir.join(&stored_name);
+
+        fs::rename(&self.path, &path).await.map_err(|e| {
+            error!("Failed to move {} to {}: {}", self.path.display(), path.display(), e);
+            ApiError::internal(format!("Failed to store file '{}'", self.filename))
+        })?;
+        metrics::counter!("uploads_processed_total", "outcome" => "stored").increment(1);
+        metrics::counter!("upload_bytes_written_total").increment(self.size);
+
+        Ok(StoredFile {
+            filename: self.filename,
+            stored_name,
+            path,
+            reused: false,
+            original_size: self.original_size,
+            size: self.size,
+        })
+    }
+}
+
+/// Remove uploads left in the staging area, e.g. by requests cut off when the server stopped
+pub async fn clear_staging(upload_dir: &Path) {
+    let path = upload_dir.join(STAGING_DIR);
+
+    match fs::remove_dir_all(&path).await {
+        Ok(()) => info!("Cleared staged uploads in {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => error!("Failed to clear staging directory {}: {}", path.display(), e),
+    }
+}
+
+/// Per-request staging directory, removed with everything left in it when dropped
+pub struct StagingDir {
+    path: PathBuf,
+}
+
+impl StagingDir {
+    /// Create a fresh staging directory inside `upload_dir`
+    pub async fn create(upload_dir: &Path) -> Result<Self, ApiError> {
+        let path = upload_dir.join(STAGING_DIR).join(Uuid::new_v4().to_string());
+
+        fs::create_dir_all(&path).await.map_err(|e| {
+            error!("Failed to create staging directory {}: {}", path.display(), e);
+            ApiError::internal("Failed to prepare upload")
+        })?;
+
+        Ok(Self { path })
+    }
+
+    /// Where the files of the request are staged
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stream a file field to disk
+    ///
+    /// Fails with `413` as soon as the file grows past `max_bytes` and with `415` as soon
+    /// as its leading bytes show a content type outside `allowed`. Only the first few
+    /// kilobytes are ever held in memory.
+    pub async fn stage_field(
+        &self,
+        mut field: Field<'_>,
+        filename: String,
+        max_bytes: u64,
+        allowed: &[String],
+    ) -> Result<StagedFile, ApiError> {
+        let path = self.path.join(Uuid::new_v4().to_string());
+        let mut file = fs::File::create(&path).await.map_err(|e| {
+            error!("Failed to create file {}: {}", path.display(), e);
+            ApiError::internal(format!("Failed to store file '{}'", filename))
+        })?;
+
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        let mut mime_type = None;
+        let mut size: u64 = 0;
+        let mut hasher = Sha256::new();
+
+        while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+            size += chunk.len() as u64;
+            if size > max_bytes {
+                warn!("Rejected upload {}: larger than {} bytes", filename, max_bytes);
+                return Err(ApiError::payload_too_large(format!(
+                    "File '{}' exceeds the maximum size of {} bytes",
+                    filename, max_bytes
+                )));
+            }
+
+            if mime_type.is_none() {
+                let take = chunk.len().min(SNIFF_BYTES - head.len());
+                head.extend_from_slice(&chunk[..take]);
+                if head.len() == SNIFF_BYTES {
+                    mime_type = Some(check_allowed_type(&filename, &head, allowed)?);
+                }
+            }
+
+            hasher.update(&chunk);
+            file.write_all(&chunk).await.map_err(|e| {
+                error!("Failed to write file {}: {}", path.display(), e);
+                ApiError::internal(format!("Failed to store file '{}'", filename))
+            })?;
+        }
+
+        let mime_type = match mime_type {
+            Some(mime_type) => mime_type,
+            None => check_allowed_type(&filename, &head, allowed)?,
+        };
+
+        file.flush().await.map_err(|e| {
+            error!("Failed to write file {}: {}", path.display(), e);
+            ApiError::internal(format!("Failed to store file '{}'", filename))
+        })?;
+
+        let content_hash: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Ok(StagedFile {
+            filename,
+            mime_type,
+            size,
+            content_hash: content_hash[..CONTENT_HASH_CHARS].to_string(),
+            original_size: size,
+            path,
+        })
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                error!("Failed to remove staging directory {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Streaming ZIP writer
+//!
+//! Writes a ZIP archive front to back without seeking, so it can be sent as a response body
+//! while it is produced. Entries are stored uncompressed (photos and videos are already
+//! compressed) and their CRC and sizes follow the data in a data descriptor. Archives are
+//! limited to 4 GiB and 65535 entries, as there is no ZIP64 support.
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// ZIP 2.0, the first version with data descriptors
+const VERSION: u16 = 20;
+/// Sizes in a data descriptor (bit 3) and UTF-8 file names (bit 11)
+const FLAGS: u16 = (1 << 3) | (1 << 11);
+const METHOD_STORED: u16 = 0;
+
+const COPY_BUFFER_BYTES: usize = 64 * 1024;
+
+struct CentralEntry {
+    name: String,
+    dos_time: u16,
+    dos_date: u16,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+pub struct ZipStreamWriter<W> {
+    out: W,
+    offset: u64,
+    entries: Vec<CentralEntry>,
+}
+
+impl<W: AsyncWrite + Unpin> ZipStreamWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Append a file named `name`, copying its content from `reader`
+    pub async fn add_file(
+        &mut self,
+        name: &str,
+        modified: DateTime<Utc>,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> io::Result<()> {
+        if self.entries.len() == usize::from(u16::MAX) {
+            return Err(too_large("more than 65535 entries"));
+        }
+        let offset = u32::try_from(self.offset).map_err(|_| too_large("larger than 4 GiB"))?;
+        let (dos_time, dos_date) = dos_date_time(modified);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&FLAGS.to_le_bytes());
+        header.extend_from_slice(&METHOD_STORED.to_le_bytes());
+        header.extend_from_slice(&dos_time.to_le_bytes());
+        header.extend_from_slice(&dos_date.to_le_bytes());
+        // CRC, compressed and uncompressed sizes are in the data descriptor
+        header.extend_from_slice(&[0; 12]);
+        header.extend_from_slice(&name_len(name)?.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header).await?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        let mut size: u64 = 0;
+        let mut buffer = vec![0; COPY_BUFFER_BYTES];
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            size += read as u64;
+            self.write(&buffer[..read]).await?;
+        }
+        let crc = hasher.finalize();
+        let size = u32::try_from(size).map_err(|_| too_large("entry larger than 4 GiB"))?;
+
+        let mut descriptor = Vec::with_capacity(16);
+        descriptor.extend_from_slice(&DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+        descriptor.extend_from_slice(&crc.to_le_bytes());
+        descriptor.extend_from_slice(&size.to_le_bytes());
+        descriptor.extend_from_slice(&size.to_le_bytes());
+        self.write(&descriptor).await?;
+
+        self.entries.push(CentralEntry {
+            name: name.to_string(),
+            dos_time,
+            dos_date,
+            crc,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    /// Write the central directory and flush the archive
+    pub async fn finish(mut self) -> io::Result<W> {
+        let directory_offset = u32::try_from(self.offset).map_err(|_| too_large("larger than 4 GiB"))?;
+
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            directory.extend_from_slice(&VERSION.to_le_bytes());
+            directory.extend_from_slice(&VERSION.to_le_bytes());
+            directory.extend_from_slice(&FLAGS.to_le_bytes());
+            directory.extend_from_slice(&METHOD_STORED.to_le_bytes());
+            directory.extend_from_slice(&entry.dos_time.to_le_bytes());
+            directory.extend_from_slice(&entry.dos_date.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&name_len(&entry.name)?.to_le_bytes());
+            // Extra field and comment lengths, disk number, internal and external attributes
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = u32::try_from(directory.len()).map_err(|_| too_large("larger than 4 GiB"))?;
+        let entry_count = self.entries.len() as u16;
+
+        directory.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        // This disk and the disk with the central directory
+        directory.extend_from_slice(&[0; 4]);
+        directory.extend_from_slice(&entry_count.to_le_bytes());
+        directory.extend_from_slice(&entry_count.to_le_bytes());
+        directory.extend_from_slice(&directory_size.to_le_bytes());
+        directory.extend_from_slice(&directory_offset.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        self.write(&directory).await?;
+
+        self.out.flush().await?;
+        Ok(self.out)
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes).await?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+fn name_len(name: &str) -> io::Result<u16> {
+    u16::try_from(name.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file name too long"))
+}
+
+fn too_large(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("ZIP archive {}", reason))
+}
+
+/// MS-DOS time and date of a timestamp, which cannot represent years before 1980
+fn dos_date_time(time: DateTime<Utc>) -> (u16, u16) {
+    if time.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+
+    let dos_time = ((time.hour() << 11) | (time.minute() << 5) | (time.second() / 2)) as u16;
+    let dos_date = ((((time.year() - 1980) as u32) << 9) | (time.month() << 5) | time.day()) as u16;
+    (dos_time, dos_date)
+}
@@ -0,0 +1,912 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use common::{album_data, assert_status, body_json, jpeg, png, Multipart, TestApp};
+use image::GenericImageView;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn album_lifecycle(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    // Create the album with a first photo
+    let form = Multipart::new()
+        .text("album_data", &album_data("lifecycle"))
+        .file("files", "first.png", "image/png", &png(1));
+    let response = app.multipart("POST", "/api/v1/albums/with-files", form).await;
+    assert_status(&response, StatusCode::CREATED);
+    assert_eq!(response.headers()["location"], "/api/v1/albums/lifecycle");
+    let created = body_json(response).await;
+    assert_eq!(created["album_slug"], "lifecycle");
+    assert_eq!(created["added_photos"].as_array().unwrap().len(), 1);
+
+    // Add two more photos
+    let form = Multipart::new()
+        .file("files", "second.png", "image/png", &png(2))
+        .file("files", "third.png", "image/png", &png(3));
+    let response = app.multipart("PUT", "/api/v1/albums/lifecycle/photos", form).await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(response).await["added_photos"].as_array().unwrap().len(), 2);
+
+    // The album lists the photos in upload order, with their dimensions and thumbnails on disk
+    let response = app.get("/api/v1/albums/lifecycle").await;
+    assert_status(&response, StatusCode::OK);
+    let album = body_json(response).await;
+    assert_eq!(album["title"], "Test album");
+    let content = album["content"].as_array().unwrap();
+    assert_eq!(content.len(), 3);
+    assert!(content[0]["caption"].as_str().unwrap().contains("first.png"));
+    assert_eq!(content[0]["width"], 64);
+    assert_eq!(content[0]["height"], 48);
+
+    let removed_url = content[1]["img_url"].as_str().unwrap().to_string();
+    let removed_file = app.path().join(removed_url.trim_start_matches("/files/"));
+    let removed_thumb = app.path().join(content[1]["thumb_url"].as_str().unwrap().trim_start_matches("/files/"));
+    assert!(removed_file.is_file());
+    // Photos smaller than the thumbnail size are their own thumbnail
+    assert_eq!(std::fs::read(&removed_thumb).unwrap(), std::fs::read(&removed_file).unwrap());
+
+    // Remove the second photo, its files go with it
+    let response = app
+        .json("DELETE", "/api/v1/albums/lifecycle/photos", json!({ "img_url": removed_url }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(response).await["removed"], 1);
+    assert!(!removed_file.exists());
+    assert!(!removed_thumb.exists());
+
+    let album = body_json(app.get("/api/v1/albums/lifecycle").await).await;
+    let remaining: Vec<&str> = album["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|photo| photo["img_url"].as_str().unwrap())
+        .collect();
+    assert_eq!(remaining.len(), 2);
+    assert!(!remaining.contains(&removed_url.as_str()));
+
+    // Deleting moves the album to the trash, from where it can be restored
+    let response = app.authorized("DELETE", "/api/v1/albums/lifecycle").await;
+    assert_status(&response, StatusCode::OK);
+    assert_status(&app.get("/api/v1/albums/lifecycle").await, StatusCode::NOT_FOUND);
+    assert!(body_json(app.get("/api/v1/albums").await).await.as_array().unwrap().is_empty());
+    assert_eq!(app.album_count("lifecycle").await, 1);
+
+    assert_status(&app.authorized("POST", "/api/v1/albums/lifecycle/restore").await, StatusCode::OK);
+    assert_eq!(body_json(app.get("/api/v1/albums/lifecycle").await).await["content"].as_array().unwrap().len(), 2);
+    assert_status(&app.authorized("POST", "/api/v1/albums/lifecycle/restore").await, StatusCode::NOT_FOUND);
+
+    // A hard delete removes the album with its files
+    let response = app.authorized("DELETE", "/api/v1/albums/lifecycle?hard=true").await;
+    assert_status(&response, StatusCode::OK);
+    assert_status(&app.get("/api/v1/albums/lifecycle").await, StatusCode::NOT_FOUND);
+    assert_eq!(app.album_count("lifecycle").await, 0);
+    assert!(!app.path().join("lifecycle").exists());
+}
+
+#[sqlx::test]
+async fn protected_routes_require_an_api_key(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .send(
+            axum::http::Request::delete("/api/v1/albums/anything")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_status(&response, StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["code"], "UNAUTHORIZED");
+
+    let response = app
+        .send(
+            axum::http::Request::delete("/api/v1/albums/anything")
+                .header("X-API-Key", "wrong-key")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_status(&response, StatusCode::UNAUTHORIZED);
+}
+
+/// A failed content insert must not leave the album row, the directory or written files behind
+#[sqlx::test]
+async fn create_with_files_rolls_back_on_failure(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    // Postgres rejects NUL bytes in text, so the second content insert fails after the
+    // first photo was written and inserted
+    let form = Multipart::new()
+        .text("album_data", &album_data("rollback"))
+        .text("captions", &json!(["fine", "broken\u{0}caption"]).to_string())
+        .file("files", "first.png", "image/png", &png(1))
+        .file("files", "second.png", "image/png", &png(2));
+    let response = app.multipart("POST", "/api/v1/albums/with-files", form).await;
+    assert_status(&response, StatusCode::INTERNAL_SERVER_ERROR);
+
+    assert_eq!(app.album_count("rollback").await, 0);
+    let content: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Album_Content WHERE slug = 'rollback'")
+        .fetch_one(&app.db)
+        .await
+        .unwrap();
+    assert_eq!(content, 0);
+    assert!(!app.path().join("rollback").exists());
+
+    // The slug is free again
+    let form = Multipart::new()
+        .text("album_data", &album_data("rollback"))
+        .file("files", "first.png", "image/png", &png(1));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+}
+
+/// Two creates racing for the same slug end with one album and one directory
+#[sqlx::test]
+async fn concurrent_creates_of_the_same_slug(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let create = |seed| {
+        let form = Multipart::new()
+            .text("album_data", &album_data("race"))
+            .file("files", "photo.png", "image/png", &png(seed));
+        app.multipart("POST", "/api/v1/albums/with-files", form)
+    };
+    let (first, second) = tokio::join!(create(1), create(2));
+
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::CONFLICT]);
+
+    assert_eq!(app.album_count("race").await, 1);
+    let content: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Album_Content WHERE slug = 'race'")
+        .fetch_one(&app.db)
+        .await
+        .unwrap();
+    assert_eq!(content, 1);
+
+    // One directory, holding only the winner's photo and its thumbnail
+    let directories: Vec<_> = std::fs::read_dir(app.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    assert_eq!(directories, ["race"]);
+    assert_eq!(std::fs::read_dir(app.path().join("race")).unwrap().count(), 2);
+}
+
+#[sqlx::test]
+async fn cover_urls_must_resolve(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let album = |slug: &str, cover: &str| {
+        let mut album: serde_json::Value = serde_json::from_str(&album_data(slug)).unwrap();
+        album["preview_img_one_url"] = json!(cover);
+        album
+    };
+
+    let response = app.json("POST", "/api/v1/albums", album("missing", "/files/missing/cover.jpg")).await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "COVER_NOT_FOUND");
+
+    let response = app.json("POST", "/api/v1/albums", album("relative", "cover.jpg")).await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "INVALID_COVER_URL");
+    assert_eq!(app.album_count("relative").await, 0);
+
+    let response = app
+        .json("POST", "/api/v1/albums", album("external", "https://cdn.example.com/cover.jpg"))
+        .await;
+    assert_status(&response, StatusCode::CREATED);
+
+    // With files, the cover can name an uploaded file
+    let form = Multipart::new()
+        .text("album_data", &album("with-cover", "second.png").to_string())
+        .file("files", "first.png", "image/png", &png(1))
+        .file("files", "second.png", "image/png", &png(2));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+
+    let created = body_json(app.get("/api/v1/albums/with-cover").await).await;
+    let cover = created["preview_img_one_url"].as_str().unwrap();
+    assert_eq!(cover, created["content"][1]["img_url"]);
+
+    // An update can point the cover at any uploaded file, but not a missing one
+    let response = app
+        .json("PUT", "/api/v1/albums/external", json!({ "preview_img_one_url": cover }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+    let response = app
+        .json("PUT", "/api/v1/albums/external", json!({ "preview_img_one_url": "/files/with-cover/gone.png" }))
+        .await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(app.get("/api/v1/albums/external").await).await["preview_img_one_url"], cover);
+}
+
+#[sqlx::test]
+async fn album_photos_are_paginated_and_sorted(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let form = Multipart::new()
+        .text("album_data", &album_data("paged"))
+        .text("captions", &json!(["Charlie", "Alpha", "Delta", "Bravo"]).to_string())
+        .file("files", "1.png", "image/png", &png(1))
+        .file("files", "2.png", "image/png", &png(2))
+        .file("files", "3.png", "image/png", &png(3))
+        .file("files", "4.png", "image/png", &png(4));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+
+    let captions = |album: &serde_json::Value| -> Vec<String> {
+        album["content"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|photo| photo["caption"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let album = body_json(app.get("/api/v1/albums/paged").await).await;
+    assert_eq!(album["total_photos"], 4);
+    assert_eq!(captions(&album), ["Charlie", "Alpha", "Delta", "Bravo"]);
+
+    let album = body_json(app.get("/api/v1/albums/paged?photo_limit=2&photo_offset=1").await).await;
+    assert_eq!(album["title"], "Test album");
+    assert_eq!(album["total_photos"], 4);
+    assert_eq!(captions(&album), ["Alpha", "Delta"]);
+
+    let album = body_json(app.get("/api/v1/albums/paged?sort=caption&photo_limit=3").await).await;
+    assert_eq!(captions(&album), ["Alpha", "Bravo", "Charlie"]);
+
+    // Past the last photo the metadata still comes back
+    let album = body_json(app.get("/api/v1/albums/paged?photo_offset=10").await).await;
+    assert_eq!(album["slug"], "paged");
+    assert_eq!(album["total_photos"], 4);
+    assert!(captions(&album).is_empty());
+
+    assert_status(&app.get("/api/v1/albums/paged?sort=random").await, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn random_album_and_photo(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_status(&app.get("/api/v1/albums/random").await, StatusCode::NOT_FOUND);
+    assert_status(&app.get("/api/v1/photos/random").await, StatusCode::NOT_FOUND);
+
+    let mut featured: serde_json::Value = serde_json::from_str(&album_data("featured")).unwrap();
+    featured["featured"] = json!(true);
+    let form = Multipart::new()
+        .text("album_data", &featured.to_string())
+        .file("files", "photo.png", "image/png", &png(1));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+    assert_status(&app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data("plain")).unwrap()).await, StatusCode::CREATED);
+
+    // Only the featured album is ever picked while there is one
+    for _ in 0..5 {
+        let response = app.get("/api/v1/albums/random").await;
+        assert_status(&response, StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "no-store");
+        let album = body_json(response).await;
+        assert_eq!(album["slug"], "featured");
+        assert_eq!(album["content"].as_array().unwrap().len(), 1);
+    }
+
+    let photo = body_json(app.get("/api/v1/photos/random").await).await;
+    assert_eq!(photo["slug"], "featured");
+    assert!(photo["img_url"].as_str().unwrap().starts_with("/files/featured/"));
+}
+
+#[sqlx::test]
+async fn photo_tags(pool: PgPool) {
+    let app = TestApp::new(pool);
+    for slug in ["tagged", "other"] {
+        let form = Multipart::new()
+            .text("album_data", &album_data(slug))
+            .file("files", "photo.png", "image/png", &png(slug.len() as u8));
+        assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+    }
+    let photo_url = |album: &serde_json::Value| album["content"][0]["img_url"].as_str().unwrap().to_string();
+    let tagged = photo_url(&body_json(app.get("/api/v1/albums/tagged").await).await);
+    let other = photo_url(&body_json(app.get("/api/v1/albums/other").await).await);
+
+    let response = app
+        .json("PUT", "/api/v1/albums/tagged/photos/tags", json!({ "img_url": tagged, "tags": ["#Sunset", "beach", "sunset"] }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(response).await["tags"], json!(["beach", "sunset"]));
+    app.json("PUT", "/api/v1/albums/other/photos/tags", json!({ "img_url": other, "tags": ["sunset"] }))
+        .await;
+
+    let response = app
+        .json("PUT", "/api/v1/albums/tagged/photos/tags", json!({ "img_url": tagged, "tags": ["two words"] }))
+        .await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "INVALID_TAG");
+    let response = app
+        .json("PUT", "/api/v1/albums/tagged/photos/tags", json!({ "img_url": other, "tags": ["beach"] }))
+        .await;
+    assert_status(&response, StatusCode::NOT_FOUND);
+
+    // Tags show up in the album and in the cross-album view
+    let album = body_json(app.get("/api/v1/albums/tagged").await).await;
+    assert_eq!(album["content"][0]["tags"], json!(["beach", "sunset"]));
+    let photos = body_json(app.get("/api/v1/photos/by-tag/SUNSET").await).await;
+    assert_eq!(photos.as_array().unwrap().len(), 2);
+    assert_eq!(body_json(app.get("/api/v1/photos/by-tag/beach").await).await.as_array().unwrap().len(), 1);
+
+    // Tags follow the photo through an album rename
+    let response = app
+        .json("POST", "/api/v1/albums/tagged/rename", json!({ "new_slug": "renamed" }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+    let photos = body_json(app.get("/api/v1/photos/by-tag/beach").await).await;
+    assert_eq!(photos[0]["slug"], "renamed");
+
+    let renamed = photo_url(&body_json(app.get("/api/v1/albums/renamed").await).await);
+    let response = app
+        .json("DELETE", "/api/v1/albums/renamed/photos/tags", json!({ "img_url": renamed, "tags": ["beach"] }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(response).await["tags"], json!(["sunset"]));
+    assert!(body_json(app.get("/api/v1/photos/by-tag/beach").await).await.as_array().unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn album_views(pool: PgPool) {
+    let app = TestApp::new(pool);
+    for slug in ["quiet", "busy"] {
+        assert_status(&app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data(slug)).unwrap()).await, StatusCode::CREATED);
+    }
+
+    for _ in 0..3 {
+        assert_status(&app.get("/api/v1/albums/busy").await, StatusCode::OK);
+    }
+    assert_status(&app.get("/api/v1/albums/quiet").await, StatusCode::OK);
+    assert_status(&app.get("/api/v1/albums/quiet?track=false").await, StatusCode::OK);
+
+    // Views are counted in the background
+    let views = |slug: &'static str| {
+        let db = app.db.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT views FROM Album_Metadata WHERE slug = $1")
+                .bind(slug)
+                .fetch_one(&db)
+                .await
+                .unwrap()
+        }
+    };
+    for _ in 0..50 {
+        if views("busy").await == 3 && views("quiet").await == 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(views("busy").await, 3);
+    assert_eq!(views("quiet").await, 1);
+
+    let popular = body_json(app.get("/api/v1/albums/popular?limit=1").await).await;
+    assert_eq!(popular.as_array().unwrap().len(), 1);
+    assert_eq!(popular[0]["slug"], "busy");
+    assert_eq!(popular[0]["views"], 3);
+}
+
+#[sqlx::test]
+async fn album_responses_support_conditional_get(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let form = Multipart::new()
+        .text("album_data", &album_data("polled"))
+        .file("files", "1.png", "image/png", &png(1))
+        .file("files", "2.png", "image/png", &png(2));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+
+    // Move everything an hour back, HTTP dates only have a one second resolution
+    sqlx::query("UPDATE Album_Metadata SET created_at = now() - interval '1 hour', updated_at = NULL")
+        .execute(&app.db)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE Album_Content SET created_at = now() - interval '1 hour'")
+        .execute(&app.db)
+        .await
+        .unwrap();
+
+    let response = app.get("/api/v1/albums/polled").await;
+    assert_status(&response, StatusCode::OK);
+    let last_modified = response.headers()[header::LAST_MODIFIED].to_str().unwrap().to_string();
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+    let album = body_json(response).await;
+
+    let polled = |since: &str| {
+        Request::get("/api/v1/albums/polled")
+            .header(header::IF_MODIFIED_SINCE, since)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.send(polled(&last_modified)).await;
+    assert_status(&response, StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::LAST_MODIFIED], last_modified.as_str());
+    assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    assert_status(&app.send(polled("not a date")).await, StatusCode::OK);
+
+    // Reordering the photos changes the album
+    let mut img_urls: Vec<_> = album["content"].as_array().unwrap().iter().map(|photo| photo["img_url"].clone()).collect();
+    img_urls.reverse();
+    let reorder = app.json("PUT", "/api/v1/albums/polled/photos/reorder", json!({ "img_urls": img_urls })).await;
+    assert_status(&reorder, StatusCode::OK);
+
+    let response = app.send(polled(&last_modified)).await;
+    assert_status(&response, StatusCode::OK);
+    assert_ne!(response.headers()[header::LAST_MODIFIED], last_modified.as_str());
+}
+
+#[sqlx::test]
+async fn lists_can_be_wrapped_in_an_envelope(pool: PgPool) {
+    let app = TestApp::new(pool);
+    for slug in ["first", "second"] {
+        assert_status(&app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data(slug)).unwrap()).await, StatusCode::CREATED);
+    }
+
+    let albums = body_json(app.get("/api/v1/albums").await).await;
+    assert_eq!(albums.as_array().unwrap().len(), 2);
+
+    let response = app.get("/api/v1/albums?envelope=true").await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let envelope = body_json(response).await;
+    assert_eq!(envelope["meta"]["count"], 2);
+    assert_eq!(envelope["data"], albums);
+
+    let request = Request::get("/api/v1/albums/categories")
+        .header(header::ACCEPT, "application/vnd.portfolio.v2+json")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/vnd.portfolio.v2+json");
+    assert_eq!(body_json(response).await, json!({ "data": ["tests"], "meta": { "count": 1 } }));
+
+    assert_status(&app.get("/api/v1/albums?envelope=maybe").await, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn unversioned_routes_are_deprecated_aliases(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_status(&app.json("POST", "/albums", serde_json::from_str(&album_data("legacy")).unwrap()).await, StatusCode::CREATED);
+
+    let response = app.get("/albums/legacy").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()["deprecation"], "true");
+    assert_eq!(response.headers()[header::LINK], "</api/v1/albums/legacy>; rel=\"successor-version\"");
+
+    let response = app.get("/api/v1/albums/legacy").await;
+    assert_status(&response, StatusCode::OK);
+    assert!(!response.headers().contains_key("deprecation"));
+
+    // Health checks and files are not versioned
+    assert_status(&app.get("/api/v1/health").await, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn slug_conflicts_return_the_existing_album(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let album: serde_json::Value = serde_json::from_str(&album_data("taken")).unwrap();
+    assert_status(&app.json("POST", "/api/v1/albums", album.clone()).await, StatusCode::CREATED);
+
+    let response = app.json("POST", "/api/v1/albums", album).await;
+    assert_status(&response, StatusCode::CONFLICT);
+    let body = body_json(response).await;
+    assert_eq!(body["code"], "ALBUM_ALREADY_EXISTS");
+    assert_eq!(body["existing"]["slug"], "taken");
+    assert_eq!(body["existing"]["title"], "Test album");
+}
+
+#[sqlx::test]
+async fn batch_delete_reports_each_album(pool: PgPool) {
+    let app = TestApp::new(pool);
+    for slug in ["batch-one", "batch-two"] {
+        let form = Multipart::new()
+            .text("album_data", &album_data(slug))
+            .file("files", "photo.png", "image/png", &png(1));
+        assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+    }
+    // A trashed album is deleted for good too
+    assert_status(&app.authorized("DELETE", "/api/v1/albums/batch-two").await, StatusCode::OK);
+
+    let response = app
+        .json("POST", "/api/v1/albums/batch-delete", json!({ "slugs": ["batch-one", "missing", "batch-two"] }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(
+        body_json(response).await["results"],
+        json!([
+            { "slug": "batch-one", "status": "deleted" },
+            { "slug": "missing", "status": "not_found" },
+            { "slug": "batch-two", "status": "deleted" },
+        ])
+    );
+
+    assert_eq!(app.album_count("batch-one").await + app.album_count("batch-two").await, 0);
+    assert!(!app.path().join("batch-one").exists());
+    assert!(!app.path().join("batch-two").exists());
+}
+
+/// Files are processed several at a time, but reported and stored in upload order
+#[sqlx::test]
+async fn batch_uploads_keep_their_order(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_status(
+        &app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data("ordered")).unwrap()).await,
+        StatusCode::CREATED,
+    );
+
+    let captions: Vec<String> = (0..10).map(|i| format!("photo {i}")).collect();
+    let form = (0..10u8).fold(Multipart::new().text("captions", &json!(captions).to_string()), |form, i| {
+        form.file("files", &format!("photo-{i}.png"), "image/png", &png(i))
+    });
+    let response = app.multipart("PUT", "/api/v1/albums/ordered/photos", form).await;
+    assert_status(&response, StatusCode::OK);
+    let body = body_json(response).await;
+    assert!(body["failed"].as_array().unwrap().is_empty());
+
+    let added: Vec<&str> = body["added_photos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|photo| photo["caption"].as_str().unwrap())
+        .collect();
+    assert_eq!(added, captions);
+
+    let album = body_json(app.get("/api/v1/albums/ordered").await).await;
+    let stored: Vec<&str> = album["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|photo| photo["caption"].as_str().unwrap())
+        .collect();
+    assert_eq!(stored, captions);
+}
+
+/// A file that cannot be added is reported without failing the rest of the upload
+#[sqlx::test]
+async fn add_photos_reports_failed_files(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_status(
+        &app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data("partial")).unwrap()).await,
+        StatusCode::CREATED,
+    );
+
+    // Postgres rejects NUL bytes in text, so only the second content insert fails
+    let form = Multipart::new()
+        .text("captions", &json!(["fine", "broken\u{0}caption", "also fine"]).to_string())
+        .file("files", "first.png", "image/png", &png(1))
+        .file("files", "second.png", "image/png", &png(2))
+        .file("files", "third.png", "image/png", &png(3));
+    let response = app.multipart("PUT", "/api/v1/albums/partial/photos", form).await;
+    assert_status(&response, StatusCode::MULTI_STATUS);
+    let body = body_json(response).await;
+    assert_eq!(body["message"], "Added 2 of 3 photos");
+    assert_eq!(body["added_photos"].as_array().unwrap().len(), 2);
+    assert_eq!(body["failed"], json!([
+        { "filename": "second.png", "code": "INTERNAL_ERROR", "error": "Failed to add album content" },
+    ]));
+
+    // The failed file is not left in the album folder
+    let files = std::fs::read_dir(app.path().join("partial"))
+        .unwrap()
+        .filter(|entry| !entry.as_ref().unwrap().file_name().to_string_lossy().contains(".thumb"))
+        .count();
+    assert_eq!(files, 2);
+    assert_eq!(body_json(app.get("/api/v1/albums/partial").await).await["content"].as_array().unwrap().len(), 2);
+}
+
+/// A retried create with the same Idempotency-Key gets the first response instead of a 409
+#[sqlx::test]
+async fn idempotency_keys_replay_creates(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let create = |key: &str| {
+        Request::post("/api/v1/albums")
+            .header("X-API-Key", common::API_KEY)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("Idempotency-Key", key)
+            .body(Body::from(album_data("retried")))
+            .unwrap()
+    };
+
+    let first = app.send(create("upload-42")).await;
+    assert_status(&first, StatusCode::CREATED);
+    assert!(first.headers().get("idempotent-replayed").is_none());
+    let first_body = body_json(first).await;
+
+    let retry = app.send(create("upload-42")).await;
+    assert_status(&retry, StatusCode::CREATED);
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    assert_eq!(retry.headers()["location"], "/api/v1/albums/retried");
+    assert_eq!(body_json(retry).await, first_body);
+    assert_eq!(app.album_count("retried").await, 1);
+
+    // Another key runs the request again
+    assert_status(&app.send(create("upload-43")).await, StatusCode::CONFLICT);
+
+    let response = app.send(create("")).await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "INVALID_IDEMPOTENCY_KEY");
+}
+
+#[sqlx::test]
+async fn albums_are_fetched_in_batch(pool: PgPool) {
+    let app = TestApp::new(pool);
+    for slug in ["batch-a", "batch-b", "batch-trashed"] {
+        let album: serde_json::Value = serde_json::from_str(&album_data(slug)).unwrap();
+        assert_status(&app.json("POST", "/api/v1/albums", album).await, StatusCode::CREATED);
+    }
+    assert_status(&app.authorized("DELETE", "/api/v1/albums/batch-trashed").await, StatusCode::OK);
+
+    let response = app.get("/api/v1/albums/batch?slugs=batch-b,unknown,batch-a,batch-trashed,batch-b").await;
+    assert_status(&response, StatusCode::OK);
+    let body = body_json(response).await;
+    let slugs: Vec<&str> = body["albums"].as_array().unwrap().iter().map(|album| album["slug"].as_str().unwrap()).collect();
+    assert_eq!(slugs, ["batch-b", "batch-a"]);
+    assert_eq!(body["missing"], json!(["unknown", "batch-trashed"]));
+
+    let response = app.get("/api/v1/albums/batch?slugs=,").await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "MISSING_SLUGS");
+
+    let too_many: Vec<String> = (0..101).map(|i| format!("album-{i}")).collect();
+    let response = app.get(&format!("/api/v1/albums/batch?slugs={}", too_many.join(","))).await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn featured_albums_are_listed_newest_first(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let response = app.get("/api/v1/albums/featured").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(response).await, json!([]));
+
+    for (slug, date, featured) in [("old", "2023-01-01", true), ("plain", "2025-01-01", false), ("new", "2024-06-01", true)] {
+        let mut album: serde_json::Value = serde_json::from_str(&album_data(slug)).unwrap();
+        album["date"] = json!(date);
+        album["featured"] = json!(featured);
+        assert_status(&app.json("POST", "/api/v1/albums", album).await, StatusCode::CREATED);
+    }
+
+    for path in ["/api/v1/albums/featured", "/api/v1/albums?featured=true"] {
+        let response = app.get(path).await;
+        assert_status(&response, StatusCode::OK);
+        let body = body_json(response).await;
+        let slugs: Vec<&str> = body.as_array().unwrap().iter().map(|album| album["slug"].as_str().unwrap()).collect();
+        assert_eq!(slugs, ["new", "old"]);
+    }
+
+    let body = body_json(app.get("/api/v1/albums?featured=false").await).await;
+    assert_eq!(body[0]["slug"], "plain");
+    assert_eq!(body.as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test]
+async fn albums_are_capped_at_max_photos(pool: PgPool) {
+    let app = TestApp::with_config(pool, |config| config.max_photos_per_album = 3);
+    let files = |seeds: &[u8], form: Multipart| {
+        seeds
+            .iter()
+            .fold(form, |form, &seed| form.file("files", &format!("photo-{seed}.png"), "image/png", &png(seed)))
+    };
+
+    let form = files(&[1, 2, 3, 4], Multipart::new().text("album_data", &album_data("too-big")));
+    let response = app.multipart("POST", "/api/v1/albums/with-files", form).await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "TOO_MANY_PHOTOS");
+
+    let form = files(&[1], Multipart::new().text("album_data", &album_data("capped")));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+
+    // A batch going past the limit is rejected whole, none of its files are written
+    let response = app.multipart("PUT", "/api/v1/albums/capped/photos", files(&[2, 3, 4], Multipart::new())).await;
+    assert_status(&response, StatusCode::CONFLICT);
+    assert_eq!(body_json(response).await["code"], "ALBUM_FULL");
+    let photos = body_json(app.get("/api/v1/albums/capped").await).await["content"].as_array().unwrap().len();
+    assert_eq!(photos, 1);
+    let stored = || {
+        std::fs::read_dir(app.path().join("capped"))
+            .unwrap()
+            .filter(|entry| !entry.as_ref().unwrap().file_name().to_string_lossy().contains(".thumb"))
+            .count()
+    };
+    assert_eq!(stored(), 1);
+
+    assert_status(&app.multipart("PUT", "/api/v1/albums/capped/photos", files(&[2], Multipart::new())).await, StatusCode::OK);
+
+    // Two batches racing for the last place: only one gets it
+    let (first, second) = tokio::join!(
+        app.multipart("PUT", "/api/v1/albums/capped/photos", files(&[5], Multipart::new())),
+        app.multipart("PUT", "/api/v1/albums/capped/photos", files(&[6], Multipart::new())),
+    );
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+    let photos = body_json(app.get("/api/v1/albums/capped").await).await["content"].as_array().unwrap().len();
+    assert_eq!(photos, 3);
+    // The losing batch was processed outside the album, its file never reaches the album directory
+    assert_eq!(stored(), 3);
+}
+
+#[sqlx::test]
+async fn jpegs_are_recompressed_on_upload(pool: PgPool) {
+    let app = TestApp::with_config(pool, |config| config.recompress_quality = Some(40));
+    assert_status(
+        &app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data("recompressed")).unwrap()).await,
+        StatusCode::CREATED,
+    );
+
+    let original = jpeg(1);
+    let form = Multipart::new()
+        .file("files", "camera.jpg", "image/jpeg", &original)
+        .file("files", "drawing.png", "image/png", &png(1));
+    let response = app.multipart("PUT", "/api/v1/albums/recompressed/photos", form).await;
+    assert_status(&response, StatusCode::OK);
+    let body = body_json(response).await;
+
+    // Only the JPEG is re-encoded, the PNG is stored as uploaded
+    let recompressed = body["recompressed"].as_array().unwrap();
+    assert_eq!(recompressed.len(), 1);
+    let photo = &body["added_photos"][0];
+    assert_eq!(recompressed[0]["img_url"], photo["img_url"]);
+    assert_eq!(recompressed[0]["original_size"], original.len());
+    let stored_size = recompressed[0]["stored_size"].as_u64().unwrap();
+    assert!(stored_size < original.len() as u64);
+
+    let stored = std::fs::read(app.path().join(photo["img_url"].as_str().unwrap().trim_start_matches("/files/"))).unwrap();
+    assert_eq!(stored.len() as u64, stored_size);
+    assert_eq!(image::load_from_memory(&stored).unwrap().dimensions(), (64, 48));
+    // The EXIF orientation is kept, so the photo is still displayed upright
+    assert_eq!((photo["width"].as_u64(), photo["height"].as_u64()), (Some(48), Some(64)));
+
+    let drawing = &body["added_photos"][1];
+    let stored = std::fs::read(app.path().join(drawing["img_url"].as_str().unwrap().trim_start_matches("/files/"))).unwrap();
+    assert_eq!(stored, png(1));
+}
+
+#[sqlx::test]
+async fn single_photos_are_fetched_with_their_details(pool: PgPool) {
+    let app = TestApp::with_config(pool, |config| config.thumbnail_sizes = vec![32]);
+    let form = Multipart::new()
+        .text("album_data", &album_data("lightbox"))
+        .text("captions", &json!(["Harbour at dusk"]).to_string())
+        .file("files", "harbour.png", "image/png", &png(1));
+    let response = app.multipart("POST", "/api/v1/albums/with-files", form).await;
+    assert_status(&response, StatusCode::CREATED);
+    let img_url = body_json(response).await["added_photos"][0]["img_url"].as_str().unwrap().to_string();
+    let filename = img_url.rsplit('/').next().unwrap();
+    let response = app
+        .json("PUT", "/api/v1/albums/lightbox/photos/tags", json!({ "img_url": img_url, "tags": ["harbour"] }))
+        .await;
+    assert_status(&response, StatusCode::OK);
+
+    let response = app.get(&format!("/api/v1/albums/lightbox/photos/{filename}")).await;
+    assert_status(&response, StatusCode::OK);
+    let photo = body_json(response).await;
+    assert_eq!(photo["img_url"], img_url.as_str());
+    assert_eq!(photo["caption"], "Harbour at dusk");
+    assert_eq!((photo["width"].as_u64(), photo["height"].as_u64()), (Some(64), Some(48)));
+    assert_eq!(photo["tags"], json!(["harbour"]));
+    assert!(photo["thumb_url"].as_str().unwrap().contains(".thumb"));
+    assert_eq!(photo["thumbnails"][0]["size"], 32);
+
+    let response = app.get("/api/v1/albums/lightbox/photos/missing.png").await;
+    assert_status(&response, StatusCode::NOT_FOUND);
+    assert_eq!(body_json(response).await["code"], "PHOTO_NOT_FOUND");
+    let response = app.get(&format!("/api/v1/albums/unknown/photos/{filename}")).await;
+    assert_eq!(body_json(response).await["code"], "ALBUM_NOT_FOUND");
+}
+
+#[sqlx::test]
+async fn updates_with_a_stale_etag_are_refused(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_status(
+        &app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data("shared")).unwrap()).await,
+        StatusCode::CREATED,
+    );
+    let response = app.get("/api/v1/albums/shared").await;
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+
+    let update = |title: &str, if_match: Option<&str>| {
+        let mut request = Request::put("/api/v1/albums/shared")
+            .header("X-API-Key", common::API_KEY)
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
+        request.body(Body::from(json!({ "title": title }).to_string())).unwrap()
+    };
+
+    // The first admin saves with the version they loaded
+    let response = app.send(update("First edit", Some(&etag))).await;
+    assert_status(&response, StatusCode::OK);
+    let new_etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+    assert_ne!(new_etag, etag);
+    assert_eq!(app.get("/api/v1/albums/shared").await.headers()[header::ETAG], new_etag.as_str());
+
+    // The second one still has the old version and does not overwrite the first edit
+    let response = app.send(update("Second edit", Some(&etag))).await;
+    assert_status(&response, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(body_json(response).await["code"], "PRECONDITION_FAILED");
+    assert_eq!(body_json(app.get("/api/v1/albums/shared").await).await["title"], "First edit");
+
+    assert_status(&app.send(update("Weak", Some(&format!("W/{new_etag}")))).await, StatusCode::PRECONDITION_FAILED);
+    assert_status(&app.send(update("Any version", Some("*"))).await, StatusCode::OK);
+    // Without If-Match the update applies as before
+    assert_status(&app.send(update("Last write wins", None)).await, StatusCode::OK);
+    assert_eq!(body_json(app.get("/api/v1/albums/shared").await).await["title"], "Last write wins");
+}
+
+#[sqlx::test]
+async fn missing_covers_are_replaced(pool: PgPool) {
+    let app = TestApp::with_config(pool, |config| config.default_cover_url = Some("/files/site/placeholder.jpg".to_string()));
+    assert_status(
+        &app.json("POST", "/api/v1/albums", serde_json::from_str(&album_data("covers")).unwrap()).await,
+        StatusCode::CREATED,
+    );
+
+    // No cover and no photo yet
+    let album = body_json(app.get("/api/v1/albums/covers").await).await;
+    assert_eq!(album["preview_img_one_url"], "/files/site/placeholder.jpg");
+    assert_eq!(album["raw_preview_url"], "");
+
+    let form = Multipart::new().file("files", "photo.png", "image/png", &png(1));
+    assert_status(&app.multipart("PUT", "/api/v1/albums/covers/photos", form).await, StatusCode::OK);
+    let album = body_json(app.get("/api/v1/albums/covers").await).await;
+    let photo = &album["content"][0];
+    let img_url = photo["img_url"].as_str().unwrap().to_string();
+    let thumb_url = photo["thumb_url"].as_str().unwrap().to_string();
+    let response = app.json("PUT", "/api/v1/albums/covers", json!({ "preview_img_one_url": img_url })).await;
+    assert_status(&response, StatusCode::OK);
+
+    // The cover file goes missing: the photo's thumbnail stands in for it
+    std::fs::remove_file(app.path().join(img_url.trim_start_matches("/files/"))).unwrap();
+    let response = app.get("/api/v1/albums/covers").await;
+    let last_modified = response.headers()[header::LAST_MODIFIED].to_str().unwrap().to_string();
+    let album = body_json(response).await;
+    assert_eq!(album["preview_img_one_url"], thumb_url);
+    assert_eq!(album["raw_preview_url"], img_url);
+    // The stand-in does not depend on the page of photos
+    let album = body_json(app.get("/api/v1/albums/covers?photo_offset=1").await).await;
+    assert_eq!(album["preview_img_one_url"], thumb_url);
+    // Nor is it hidden behind a 304, Last-Modified does not change with it
+    let request = Request::get("/api/v1/albums/covers")
+        .header(header::IF_MODIFIED_SINCE, &last_modified)
+        .body(Body::empty())
+        .unwrap();
+    assert_status(&app.send(request).await, StatusCode::OK);
+    let albums = body_json(app.get("/api/v1/albums").await).await;
+    assert_eq!(albums[0]["preview_img_one_url"], thumb_url);
+    assert_eq!(albums[0]["raw_preview_url"], img_url);
+
+    // With the first photo's thumbnail gone too, the next photo found on disk stands in
+    let form = Multipart::new().file("files", "second.png", "image/png", &png(2));
+    assert_status(&app.multipart("PUT", "/api/v1/albums/covers/photos", form).await, StatusCode::OK);
+    std::fs::remove_file(app.path().join(thumb_url.trim_start_matches("/files/"))).unwrap();
+    let album = body_json(app.get("/api/v1/albums/covers").await).await;
+    let second_thumb_url = album["content"][1]["thumb_url"].as_str().unwrap().to_string();
+    assert_eq!(album["preview_img_one_url"], second_thumb_url);
+    let album = body_json(app.get("/api/v1/albums/covers?photo_limit=1").await).await;
+    assert_eq!(album["preview_img_one_url"], second_thumb_url);
+    let albums = body_json(app.get("/api/v1/albums").await).await;
+    assert_eq!(albums[0]["preview_img_one_url"], second_thumb_url);
+}
+
+#[sqlx::test]
+async fn search_highlights_escape_captions(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let form = Multipart::new()
+        .text("album_data", &album_data("escaped"))
+        .file("files", "photo.png", "image/png", &png(1));
+    assert_status(&app.multipart("POST", "/api/v1/albums/with-files", form).await, StatusCode::CREATED);
+    sqlx::query("UPDATE Album_Content SET caption = $1")
+        .bind(r#"Sunset <img src=x onerror="alert(1)"> & l'été"#)
+        .execute(&app.db)
+        .await
+        .unwrap();
+
+    let results = body_json(app.get("/api/v1/albums/search?q=sunset").await).await;
+    let photo = &results[0]["matched_photos"][0];
+    assert_eq!(
+        photo["highlighted_caption"],
+        "<mark>Sunset</mark> &lt;img src=x onerror=&quot;alert(1)&quot;&gt; &amp; l&#39;été"
+    );
+}
@@ -0,0 +1,212 @@
+//! Test harness: the real router on a throwaway database and upload directory
+//!
+//! Tests use `#[sqlx::test]`, which creates a fresh database per test on the server
+//! named by `DATABASE_URL` (the user needs the CREATEDB privilege) and applies the
+//! migrations before handing over the pool.
+
+#![allow(dead_code)]
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{header, Request, Response, StatusCode},
+    Router,
+};
+use portfolio_server::{app, config::Config, AppState};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::{io::Cursor, net::SocketAddr, path::Path, sync::Arc};
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+pub const API_KEY: &str = "test-api-key";
+
+const BOUNDARY: &str = "portfolio-test-boundary";
+
+pub struct TestApp {
+    pub router: Router,
+    pub db: PgPool,
+    pub upload_dir: TempDir,
+}
+
+impl TestApp {
+    pub fn new(db: PgPool) -> Self {
+        Self::with_config(db, |_| {})
+    }
+
+    /// An app whose default test configuration is adjusted by `configure`
+    pub fn with_config(db: PgPool, configure: impl FnOnce(&mut Config)) -> Self {
+        let upload_dir = tempfile::tempdir().expect("create upload directory");
+        let mut config = Config {
+            upload_dir: upload_dir.path().to_path_buf(),
+            api_key: Some(API_KEY.to_string()),
+            ..Config::default()
+        };
+        configure(&mut config);
+        let state = AppState {
+            db: db.clone(),
+            config: Arc::new(config),
+            disk_usage: Default::default(),
+        };
+
+        Self {
+            router: app(state),
+            db,
+            upload_dir,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.upload_dir.path()
+    }
+
+    /// Send a request, with the peer address the upload rate limiter expects
+    pub async fn send(&self, mut request: Request<Body>) -> Response<Body> {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        self.router.clone().oneshot(request).await.expect("router is infallible")
+    }
+
+    pub async fn get(&self, uri: &str) -> Response<Body> {
+        self.send(Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    /// Send an authenticated JSON request
+    pub async fn json(&self, method: &str, uri: &str, body: Value) -> Response<Body> {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-API-Key", API_KEY)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        self.send(request).await
+    }
+
+    /// Send an authenticated request without a body
+    pub async fn authorized(&self, method: &str, uri: &str) -> Response<Body> {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-API-Key", API_KEY)
+            .body(Body::empty())
+            .unwrap();
+        self.send(request).await
+    }
+
+    /// Send an authenticated `multipart/form-data` request
+    pub async fn multipart(&self, method: &str, uri: &str, form: Multipart) -> Response<Body> {
+        self.send(form.into_request(method, uri)).await
+    }
+
+    pub async fn album_count(&self, slug: &str) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM Album_Metadata WHERE slug = $1")
+            .bind(slug)
+            .fetch_one(&self.db)
+            .await
+            .expect("count albums")
+    }
+}
+
+/// Builder for `multipart/form-data` bodies
+#[derive(Default)]
+pub struct Multipart {
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.body.extend_from_slice(
+            format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n").as_bytes(),
+        );
+        self
+    }
+
+    pub fn file(mut self, name: &str, filename: &str, content_type: &str, data: &[u8]) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"{filename}\"\r\nContent-Type: {content_type}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    fn into_request(mut self, method: &str, uri: &str) -> Request<Body> {
+        self.body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-API-Key", API_KEY)
+            .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}"))
+            .body(Body::from(self.body))
+            .unwrap()
+    }
+}
+
+/// A small PNG, `seed` makes its bytes (and so its content hash) unique
+pub fn png(seed: u8) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([seed, x as u8, y as u8]));
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+        .expect("encode PNG");
+    bytes
+}
+
+/// A noisy JPEG at quality 100, rotated a quarter turn by its EXIF `Orientation` (6), so it
+/// is displayed 48x64
+pub fn jpeg(seed: u8) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(64, 48, |x, y| {
+        image::Rgb([seed ^ (x * 31 + y * 17) as u8, (x * y) as u8, ((x ^ y) * 5) as u8])
+    });
+    let mut encoded = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut encoded), image::ImageOutputFormat::Jpeg(100))
+        .expect("encode JPEG");
+
+    // Big-endian TIFF header with a single IFD entry: Orientation (0x0112), SHORT, 6
+    let exif: &[u8] = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0";
+    let mut bytes = encoded[..2].to_vec();
+    bytes.extend_from_slice(&[0xFF, 0xE1]);
+    bytes.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+    bytes.extend_from_slice(exif);
+    bytes.extend_from_slice(&encoded[2..]);
+    bytes
+}
+
+/// `album_data` field of an album create request
+pub fn album_data(slug: &str) -> String {
+    serde_json::json!({
+        "slug": slug,
+        "title": "Test album",
+        "description": "Created by the integration tests",
+        "short_title": "Test",
+        "date": "2024-05-01",
+        "preview_img_one_url": "",
+        "featured": false,
+        "category": "tests",
+    })
+    .to_string()
+}
+
+pub async fn body_json(response: Response<Body>) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.expect("read body");
+    serde_json::from_slice(&bytes).expect("JSON body")
+}
+
+pub async fn body_bytes(response: Response<Body>) -> Vec<u8> {
+    to_bytes(response.into_body(), usize::MAX).await.expect("read body").to_vec()
+}
+
+#[track_caller]
+pub fn assert_status(response: &Response<Body>, expected: StatusCode) {
+    assert_eq!(response.status(), expected, "unexpected status for response {:?}", response);
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use common::{assert_status, body_json, TestApp};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+fn project(slug: &str, techs: &[&str]) -> Value {
+    json!({
+        "slug": slug,
+        "translations": {
+            "en": { "title": "Test project", "short_description": "Created by the integration tests" },
+            "fr": { "title": "Projet de test", "short_description": "Créé par les tests d'intégration" },
+        },
+        "techs": techs,
+        "link": "https://example.com",
+        "date": "2024-05-01",
+        "tags": ["tests"],
+    })
+}
+
+#[sqlx::test]
+async fn techs_are_counted_across_projects(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_eq!(body_json(app.get("/api/v1/dev-projects/techs").await).await, json!([]));
+
+    // The older project's spelling loses
+    let mut older = project("older", &["rust", "PostgreSQL", "RUST"]);
+    older["date"] = json!("2023-01-01");
+    assert_status(&app.json("POST", "/api/v1/dev-projects", older).await, StatusCode::CREATED);
+    assert_status(&app.json("POST", "/api/v1/dev-projects", project("newer", &["Rust", "Axum"])).await, StatusCode::CREATED);
+
+    let response = app.get("/api/v1/dev-projects/techs").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(
+        body_json(response).await,
+        json!([
+            { "name": "Axum", "count": 1 },
+            { "name": "PostgreSQL", "count": 1 },
+            { "name": "Rust", "count": 2 },
+        ])
+    );
+}
+
+#[sqlx::test]
+async fn projects_are_localized(pool: PgPool) {
+    let app = TestApp::new(pool);
+    assert_status(&app.json("POST", "/api/v1/dev-projects", project("localized", &["Rust"])).await, StatusCode::CREATED);
+
+    let response = app.get("/api/v1/dev-projects/localized").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_LANGUAGE], "en");
+    let project = body_json(response).await;
+    assert_eq!(project["title"], "Test project");
+    assert!(project.get("fr_title").is_none());
+
+    let request = Request::get("/api/v1/dev-projects")
+        .header(header::ACCEPT_LANGUAGE, "de-DE, fr-CA;q=0.8, en;q=0.5")
+        .body(Body::empty())
+        .unwrap();
+    let projects = body_json(app.send(request).await).await;
+    assert_eq!(projects[0]["lang"], "fr");
+    assert_eq!(projects[0]["short_description"], "Créé par les tests d'intégration");
+
+    // The query parameter wins over the header
+    let request = Request::get("/api/v1/dev-projects/search?q=test&lang=en")
+        .header(header::ACCEPT_LANGUAGE, "fr")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(body_json(app.send(request).await).await[0]["title"], "Test project");
+
+    let project = body_json(app.get("/api/v1/dev-projects/localized?lang=all").await).await;
+    assert_eq!(project["translations"]["fr"]["title"], "Projet de test");
+    assert_eq!(project["translations"]["en"]["title"], "Test project");
+
+    // Missing languages fall back to English
+    let response = app.get("/api/v1/dev-projects/localized?lang=de").await;
+    assert_eq!(response.headers()[header::CONTENT_LANGUAGE], "en");
+    assert_eq!(body_json(response).await["lang"], "en");
+
+    assert_status(&app.get("/api/v1/dev-projects?lang=not_a_language").await, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn translations_can_be_added_and_removed(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    // The former en/fr fields still work
+    let mut legacy = project("legacy", &["Rust"]);
+    legacy.as_object_mut().unwrap().remove("translations");
+    legacy["en_title"] = json!("Legacy");
+    legacy["en_short_description"] = json!("Old format");
+    legacy["fr_title"] = json!("Ancien");
+    legacy["fr_short_description"] = json!("Ancien format");
+    assert_status(&app.json("POST", "/api/v1/dev-projects", legacy).await, StatusCode::CREATED);
+
+    let update = json!({
+        "translations": { "es": { "title": "Legado", "short_description": "Formato antiguo" }, "fr": null },
+        "en_title": "Legacy project",
+    });
+    assert_status(&app.json("PUT", "/api/v1/dev-projects/legacy", update).await, StatusCode::OK);
+
+    let project = body_json(app.get("/api/v1/dev-projects/legacy?lang=all").await).await;
+    assert_eq!(
+        project["translations"],
+        json!({
+            "en": { "title": "Legacy project", "short_description": "Old format" },
+            "es": { "title": "Legado", "short_description": "Formato antiguo" },
+        })
+    );
+    assert_eq!(body_json(app.get("/api/v1/dev-projects/legacy?lang=es").await).await["title"], "Legado");
+
+    // English is the fallback, it cannot go
+    let response = app.json("PUT", "/api/v1/dev-projects/legacy", json!({ "translations": { "en": null } })).await;
+    assert_status(&response, StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["code"], "MISSING_TRANSLATION");
+
+    // Renaming keeps the translations
+    let response = app.json("POST", "/api/v1/dev-projects/legacy/rename", json!({ "new_slug": "renamed" })).await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(app.get("/api/v1/dev-projects/renamed?lang=es").await).await["title"], "Legado");
+}
+
+#[sqlx::test]
+async fn projects_are_paginated_with_cursors(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let create = |slug: &str, priority: i32, date: &str| {
+        let mut project = project(slug, &["Rust"]);
+        project["priority"] = json!(priority);
+        project["date"] = json!(date);
+        project
+    };
+    for (slug, priority, date) in [
+        ("first", 1, "2024-03-01"),
+        ("second", 1, "2024-01-01"),
+        ("third-a", 2, "2024-01-01"),
+        ("third-b", 2, "2024-01-01"),
+        ("fifth", 3, "2020-01-01"),
+    ] {
+        assert_status(&app.json("POST", "/api/v1/dev-projects", create(slug, priority, date)).await, StatusCode::CREATED);
+    }
+    let slugs = |page: &Value| -> Vec<String> {
+        page["data"].as_array().unwrap().iter().map(|project| project["slug"].as_str().unwrap().to_string()).collect()
+    };
+
+    let response = app.get("/api/v1/dev-projects?limit=2&envelope=true").await;
+    let cursor = response.headers()["x-next-cursor"].to_str().unwrap().to_string();
+    let page = body_json(response).await;
+    assert_eq!(slugs(&page), ["first", "second"]);
+    assert_eq!(page["meta"]["next_cursor"], cursor.as_str());
+
+    // A project added before the cursor shifts offsets, not cursors
+    assert_status(&app.json("POST", "/api/v1/dev-projects", create("new", 0, "2025-01-01")).await, StatusCode::CREATED);
+
+    let page = body_json(app.get(&format!("/api/v1/dev-projects?limit=2&envelope=true&cursor={cursor}")).await).await;
+    assert_eq!(slugs(&page), ["third-a", "third-b"]);
+    let cursor = page["meta"]["next_cursor"].as_str().unwrap().to_string();
+
+    let response = app.get(&format!("/api/v1/dev-projects?limit=2&envelope=true&cursor={cursor}")).await;
+    assert!(response.headers().get("x-next-cursor").is_none());
+    let page = body_json(response).await;
+    assert_eq!(slugs(&page), ["fifth"]);
+    assert!(page["meta"].get("next_cursor").is_none());
+
+    // Offsets still work, and bare arrays stay bare
+    let page = body_json(app.get("/api/v1/dev-projects?limit=2&offset=2").await).await;
+    let page: Vec<&str> = page.as_array().unwrap().iter().map(|project| project["slug"].as_str().unwrap()).collect();
+    assert_eq!(page, ["second", "third-a"]);
+
+    for query in ["cursor=nonsense", "cursor=1.2024-01-01.second&offset=2", "limit=0"] {
+        let response = app.get(&format!("/api/v1/dev-projects?{query}")).await;
+        assert_status(&response, StatusCode::BAD_REQUEST);
+    }
+}
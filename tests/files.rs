@@ -0,0 +1,240 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use common::{assert_status, body_bytes, body_json, png, Multipart, TestApp};
+use sqlx::PgPool;
+
+fn clip() -> Vec<u8> {
+    (0..4096u32).map(|i| (i % 251) as u8).collect()
+}
+
+#[sqlx::test]
+async fn range_requests_return_partial_content(pool: PgPool) {
+    let app = TestApp::new(pool);
+    std::fs::create_dir(app.path().join("clips")).unwrap();
+    std::fs::write(app.path().join("clips/clip.mp4"), clip()).unwrap();
+
+    let response = app.get("/files/clips/clip.mp4").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+    let etag = response.headers()[header::ETAG].clone();
+
+    let request = Request::get("/files/clips/clip.mp4")
+        .header(header::RANGE, "bytes=0-1023")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_status(&response, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 0-1023/4096");
+    assert_eq!(response.headers()[header::ETAG], etag);
+    assert_eq!(body_bytes(response).await, clip()[..1024]);
+
+    let request = Request::get("/files/clips/clip.mp4")
+        .header(header::RANGE, "bytes=5000-")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_status(&response, StatusCode::RANGE_NOT_SATISFIABLE);
+}
+
+#[sqlx::test]
+async fn hidden_directories_are_not_served(pool: PgPool) {
+    let app = TestApp::new(pool);
+    std::fs::create_dir(app.path().join(".staging-test")).unwrap();
+    std::fs::write(app.path().join(".staging-test/upload.bin"), b"in flight").unwrap();
+
+    assert_status(&app.get("/files/.staging-test/upload.bin").await, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn webp_copies_are_served_to_clients_that_accept_them(pool: PgPool) {
+    let app = TestApp::new(pool);
+    std::fs::create_dir(app.path().join("album")).unwrap();
+    std::fs::write(app.path().join("album/photo.jpg"), b"jpeg bytes").unwrap();
+    std::fs::write(app.path().join("album/photo.jpg.webp"), b"webp").unwrap();
+    std::fs::write(app.path().join("album/plain.jpg"), b"jpeg bytes").unwrap();
+
+    let get = |uri: &str, accept: &str| {
+        Request::get(uri)
+            .header(header::ACCEPT, accept)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.send(get("/files/album/photo.jpg", "image/avif,image/webp,*/*;q=0.8")).await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/webp");
+    assert!(varies_on_accept(&response));
+    assert_eq!(body_bytes(response).await, b"webp");
+
+    for accept in ["image/*,*/*;q=0.8", "image/webp;q=0"] {
+        let response = app.send(get("/files/album/photo.jpg", accept)).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+        assert!(varies_on_accept(&response));
+        assert_eq!(body_bytes(response).await, b"jpeg bytes");
+    }
+
+    // Files without a WebP copy are served as before
+    let response = app.send(get("/files/album/plain.jpg", "image/webp")).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+    assert!(!varies_on_accept(&response));
+}
+
+fn varies_on_accept(response: &axum::http::Response<Body>) -> bool {
+    response.headers().get_all(header::VARY).iter().any(|value| value == "accept")
+}
+
+#[sqlx::test]
+async fn head_requests_return_headers_only(pool: PgPool) {
+    let app = TestApp::new(pool);
+    std::fs::create_dir(app.path().join("clips")).unwrap();
+    std::fs::write(app.path().join("clips/clip.mp4"), clip()).unwrap();
+    std::fs::write(app.path().join("clips/still.webp"), b"webp").unwrap();
+
+    let head = |uri: &str| Request::head(uri).body(Body::empty()).unwrap();
+
+    let response = app.send(head("/files/clips/clip.mp4")).await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "video/mp4");
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "4096");
+    assert!(body_bytes(response).await.is_empty());
+
+    let response = app.send(head("/files/clips/still.webp")).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/webp");
+
+    assert_status(&app.send(head("/files/clips/missing.mp4")).await, StatusCode::NOT_FOUND);
+}
+
+/// Uploads land in the configured directory and `/files` serves them from there
+#[sqlx::test]
+async fn uploads_are_served_from_the_upload_dir(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let form = Multipart::new().text("slug", "configured").file("file", "photo.png", "image/png", &png(7));
+    let response = app.multipart("POST", "/api/v1/upload", form).await;
+    assert_status(&response, StatusCode::OK);
+    let url = body_json(response).await["files"][0]["url"].as_str().unwrap().to_string();
+
+    let stored = app.path().join(url.trim_start_matches("/files/"));
+    assert_eq!(std::fs::read(&stored).unwrap(), png(7));
+
+    let response = app.get(&url).await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_bytes(response).await, png(7));
+}
+
+#[sqlx::test]
+async fn files_are_cached_as_immutable(pool: PgPool) {
+    let app = TestApp::new(pool);
+    std::fs::create_dir(app.path().join("clips")).unwrap();
+    std::fs::write(app.path().join("clips/clip.mp4"), clip()).unwrap();
+
+    let response = app.get("/files/clips/clip.mp4").await;
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=31536000, immutable");
+    let etag = response.headers()[header::ETAG].clone();
+
+    let request = Request::get("/files/clips/clip.mp4")
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_status(&response, StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=31536000, immutable");
+
+    // Missing files and the JSON API are not cached
+    let response = app.get("/files/clips/missing.mp4").await;
+    assert_status(&response, StatusCode::NOT_FOUND);
+    assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+    assert!(app.get("/api/v1/albums").await.headers().get(header::CACHE_CONTROL).is_none());
+}
+
+#[sqlx::test]
+async fn svg_uploads_are_served_precompressed(pool: PgPool) {
+    let app = TestApp::with_config(pool, |config| {
+        config.precompress_uploads = true;
+        config.allowed_mime_types.push("image/svg+xml".to_string());
+    });
+    let svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">{}</svg>",
+        "<rect width=\"10\" height=\"10\"/>".repeat(50)
+    );
+    let form = Multipart::new()
+        .text("slug", "vectors")
+        .file("file", "logo.svg", "image/svg+xml", svg.as_bytes())
+        .file("file", "photo.png", "image/png", &png(1));
+    let response = app.multipart("POST", "/api/v1/upload", form).await;
+    assert_status(&response, StatusCode::OK);
+    let body = body_json(response).await;
+    let svg_url = body["files"][0]["url"].as_str().unwrap().to_string();
+    let png_name = body["files"][1]["filename"].as_str().unwrap();
+
+    let svg_path = app.path().join(svg_url.trim_start_matches("/files/"));
+    assert!(svg_path.with_extension("svg.gz").exists());
+    assert!(svg_path.with_extension("svg.br").exists());
+    assert!(!app.path().join("vectors").join(format!("{png_name}.gz")).exists());
+
+    let get = |encoding: &str| {
+        Request::get(&svg_url)
+            .header(header::ACCEPT_ENCODING, encoding)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.send(get("gzip")).await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+    assert!(response.headers().get_all(header::VARY).iter().any(|value| value == "accept-encoding"));
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+    assert!(etag.ends_with("-gzip\""));
+    let mut decoded = String::new();
+    std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&body_bytes(response).await[..]), &mut decoded).unwrap();
+    assert_eq!(decoded, svg);
+
+    let response = app.send(get("br, gzip")).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+    assert_ne!(response.headers()[header::ETAG], etag.as_str());
+
+    let request = Request::get(&svg_url)
+        .header(header::ACCEPT_ENCODING, "gzip")
+        .header(header::IF_NONE_MATCH, &etag)
+        .body(Body::empty())
+        .unwrap();
+    assert_status(&app.send(request).await, StatusCode::NOT_MODIFIED);
+
+    // Clients that do not accept an encoding get the file itself
+    let response = app.send(get("identity")).await;
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(body_bytes(response).await, svg.as_bytes());
+}
+
+#[sqlx::test]
+async fn missing_originals_fall_back_to_their_thumbnail(pool: PgPool) {
+    let app = TestApp::with_config(pool, |config| config.serve_thumbnail_fallback = true);
+    std::fs::create_dir(app.path().join("album")).unwrap();
+    std::fs::write(app.path().join("album/photo.thumb.png"), png(1)).unwrap();
+    std::fs::write(app.path().join("album/other.png"), png(2)).unwrap();
+
+    let response = app.get("/files/album/photo.png").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()["x-fallback"], "thumbnail");
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+    assert_eq!(body_bytes(response).await, png(1));
+
+    let response = app.get("/files/album/other.thumb.png").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(response.headers()["x-fallback"], "original");
+    assert_eq!(body_bytes(response).await, png(2));
+
+    // Files that exist are served as usual, and files with nothing to stand in for them are still missing
+    let response = app.get("/files/album/other.png").await;
+    assert!(response.headers().get("x-fallback").is_none());
+    assert_status(&app.get("/files/album/missing.png").await, StatusCode::NOT_FOUND);
+
+    let app = TestApp::new(app.db.clone());
+    std::fs::create_dir(app.path().join("album")).unwrap();
+    std::fs::write(app.path().join("album/photo.thumb.png"), png(1)).unwrap();
+    assert_status(&app.get("/files/album/photo.png").await, StatusCode::NOT_FOUND);
+}
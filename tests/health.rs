@@ -0,0 +1,36 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{assert_status, body_json, TestApp};
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn probes_report_liveness_and_readiness(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app.get("/livez").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(body_json(response).await, json!({ "status": "ok" }));
+
+    let response = app.get("/readyz").await;
+    assert_status(&response, StatusCode::OK);
+    assert_eq!(
+        body_json(response).await,
+        json!({ "status": "ok", "checks": { "database": { "status": "ok" }, "upload_dir": { "status": "ok" } } })
+    );
+    // The probe file does not stay behind
+    assert_eq!(std::fs::read_dir(app.path()).unwrap().count(), 0);
+
+    // Without its upload directory the service is alive but not ready
+    std::fs::remove_dir_all(app.path()).unwrap();
+    assert_status(&app.get("/livez").await, StatusCode::OK);
+
+    let response = app.get("/readyz").await;
+    assert_status(&response, StatusCode::SERVICE_UNAVAILABLE);
+    let body = body_json(response).await;
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+    assert_eq!(body["checks"]["upload_dir"]["status"], "failed");
+    assert!(body["checks"]["upload_dir"]["error"].is_string());
+}